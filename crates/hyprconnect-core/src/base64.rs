@@ -0,0 +1,15 @@
+//! Standard base64 for binary fields in IPC messages, used with
+//! `#[serde(with = "crate::base64")]`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&STANDARD.encode(data))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    STANDARD.decode(text).map_err(de::Error::custom)
+}
@@ -1,10 +1,15 @@
 use std::fs;
 use std::path::PathBuf;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+mod base64;
+pub mod rules;
+
+pub use rules::{NotificationRules, NotificationUrgency, RuleDecision};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceState {
     pub id: String,
@@ -17,6 +22,217 @@ pub struct DeviceState {
     pub charging: Option<bool>,
     pub signal_percent: Option<u8>,
     pub network_type: Option<String>,
+    #[serde(default)]
+    pub in_call: bool,
+}
+
+/// A notification posted on the phone and exposed by the KDE Connect notifications plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhoneNotification {
+    pub device: String,
+    pub id: String,
+    pub app_name: String,
+    pub title: String,
+    pub text: String,
+    pub icon_path: Option<String>,
+    pub dismissable: bool,
+    pub reply_id: Option<String>,
+}
+
+/// A phone notification as kept in the on-disk notification history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub device: String,
+    pub id: String,
+    pub app_name: String,
+    pub title: String,
+    pub text: String,
+    pub posted_at: DateTime<Utc>,
+    pub dismissed: bool,
+}
+
+/// A single SMS/MMS message as reported by the KDE Connect conversations interface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsMessage {
+    pub thread_id: i64,
+    pub uid: i32,
+    pub addresses: Vec<String>,
+    pub body: String,
+    pub date: DateTime<Utc>,
+    pub incoming: bool,
+    pub read: bool,
+    /// Contact names for `addresses`, in the same order; empty entries are unknown numbers.
+    #[serde(default)]
+    pub contacts: Vec<String>,
+}
+
+/// Outcome of sharing one file of a `ShareFiles` request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileShareResult {
+    pub path: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A KDE Connect file transfer, as reported through the KDE job progress interface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transfer {
+    pub id: u32,
+    /// Device id, when the job title names a known device.
+    pub device: Option<String>,
+    pub title: String,
+    pub file: Option<String>,
+    /// Received from the phone rather than sent to it.
+    #[serde(default)]
+    pub incoming: bool,
+    pub state: TransferState,
+    pub processed_bytes: u64,
+    pub total_bytes: Option<u64>,
+    /// Bytes per second.
+    pub speed: u64,
+    pub percent: Option<u32>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// What the daemon does with URLs shared from the phone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlOpenPolicy {
+    /// Open immediately with `xdg-open`.
+    Always,
+    /// Post a notification with an "Open" action.
+    #[default]
+    Ask,
+    /// Notify only.
+    Never,
+}
+
+/// Moves received files matching `mime` or `extensions` into `destination`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomingRoute {
+    /// MIME type such as `application/pdf`; `image/*` matches a whole top-level type.
+    #[serde(default)]
+    pub mime: Option<String>,
+    /// File extensions without the dot, compared case-insensitively.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Target directory; a leading `~/` is expanded to the home directory.
+    pub destination: String,
+}
+
+impl IncomingRoute {
+    pub fn matches(&self, mime: Option<&str>, extension: Option<&str>) -> bool {
+        let mime_match = match (&self.mime, mime) {
+            (Some(pattern), Some(mime)) => match pattern.strip_suffix("/*") {
+                Some(top) => mime
+                    .split_once('/')
+                    .is_some_and(|(t, _)| t.eq_ignore_ascii_case(top)),
+                None => pattern.eq_ignore_ascii_case(mime),
+            },
+            _ => false,
+        };
+        let extension_match = extension.is_some_and(|ext| {
+            self.extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(ext))
+        });
+        mime_match || extension_match
+    }
+}
+
+/// Playback state of a phone's active player, as reported by the mprisremote plugin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaState {
+    pub device: String,
+    pub player: Option<String>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub playing: bool,
+    /// Percent, 0-100.
+    pub volume: Option<u8>,
+    pub position_ms: Option<u64>,
+    pub length_ms: Option<u64>,
+    pub can_seek: bool,
+    pub album_art_url: Option<String>,
+    /// Local copy of the album art under `${XDG_RUNTIME_DIR}/hyprconnect/media/`.
+    pub album_art_path: Option<String>,
+}
+
+/// One player in `MediaAction::ListAll`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaPlayerEntry {
+    pub device_name: String,
+    /// Whether this is the device's current player, which plain `media` commands act on.
+    pub active: bool,
+    #[serde(flatten)]
+    pub state: MediaState,
+}
+
+/// Reply to `MediaAction::Watch`: the device's media state as of `generation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaUpdate {
+    /// Bumped by the daemon whenever any device's media state changes.
+    pub generation: u64,
+    /// `None` when the device is unreachable or reports no player.
+    pub state: Option<MediaState>,
+}
+
+/// A share or ping request waiting for its device to become reachable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub device: String,
+    /// Short description such as `file report.pdf` or `ping`.
+    pub summary: String,
+    pub request: IpcRequest,
+    pub queued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// One action handled by the daemon, as kept in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    /// IPC request type, e.g. `share_file` or `ping`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub device: Option<String>,
+    pub summary: String,
+    pub ok: bool,
+    pub message: Option<String>,
+    /// The original request for shares and pings, kept so they can be re-sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<IpcRequest>,
+}
+
+/// Clipboard contents as read with `wl-paste --type <mime>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardCapture {
+    pub mime: String,
+    #[serde(with = "crate::base64")]
+    pub data: Vec<u8>,
+}
+
+/// An address book entry synced from the phone's contacts plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub uid: String,
+    pub name: String,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -26,12 +242,46 @@ pub struct DaemonState {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub default_device: Option<String>,
     pub poll_interval_seconds: u64,
     pub battery_warn_percent: u8,
     pub battery_crit_percent: u8,
     pub notifications_enabled: bool,
+    pub phone_notifications_enabled: bool,
+    pub reply_prompt_command: String,
+    pub notification_rules: NotificationRules,
+    pub notification_history_limit: usize,
+    pub sms_notifications_enabled: bool,
+    pub call_notifications_enabled: bool,
+    pub pause_media_during_calls: bool,
+    /// A call with no further telephony event is considered over after this many minutes; 0 disables.
+    pub call_timeout_minutes: u64,
+    pub contacts_sync_enabled: bool,
+    pub clipboard_sync_enabled: bool,
+    /// Device ids taking part in clipboard sync; empty means every paired device.
+    pub clipboard_sync_devices: Vec<String>,
+    pub clipboard_max_bytes: usize,
+    pub transfer_notifications_enabled: bool,
+    pub incoming_notifications_enabled: bool,
+    /// Evaluated in order; the first matching route decides where a received file goes.
+    pub incoming_routes: Vec<IncomingRoute>,
+    pub received_url_policy: UrlOpenPolicy,
+    /// Queued actions not delivered within this many hours are dropped.
+    pub queue_expiry_hours: u64,
+    pub audit_log_enabled: bool,
+    /// Size at which the audit log is rotated; three rotated files are kept.
+    pub audit_log_max_bytes: u64,
+    /// Now-playing text longer than this many characters is truncated in `waybar-media-json`.
+    pub media_waybar_max_chars: usize,
+    pub media_notifications_enabled: bool,
+    /// Player names (as in `media player-list`) that get track-change notifications; empty means all.
+    pub media_notification_players: Vec<String>,
+    pub state_file_enabled: bool,
+    pub state_text_template: Option<String>,
+    pub metrics_textfile: Option<String>,
+    pub metrics_listen: Option<String>,
 }
 
 impl Default for Config {
@@ -42,6 +292,32 @@ impl Default for Config {
             battery_warn_percent: 30,
             battery_crit_percent: 15,
             notifications_enabled: true,
+            phone_notifications_enabled: true,
+            reply_prompt_command: "fuzzel --dmenu --lines 0 --prompt 'Reply: '".to_string(),
+            notification_rules: NotificationRules::default(),
+            notification_history_limit: 500,
+            sms_notifications_enabled: true,
+            call_notifications_enabled: true,
+            pause_media_during_calls: false,
+            call_timeout_minutes: 60,
+            contacts_sync_enabled: true,
+            clipboard_sync_enabled: false,
+            clipboard_sync_devices: Vec::new(),
+            clipboard_max_bytes: 262_144,
+            transfer_notifications_enabled: true,
+            incoming_notifications_enabled: true,
+            incoming_routes: Vec::new(),
+            received_url_policy: UrlOpenPolicy::Ask,
+            queue_expiry_hours: 24,
+            audit_log_enabled: true,
+            audit_log_max_bytes: 1_048_576,
+            media_waybar_max_chars: 40,
+            media_notifications_enabled: false,
+            media_notification_players: Vec::new(),
+            state_file_enabled: true,
+            state_text_template: None,
+            metrics_textfile: None,
+            metrics_listen: None,
         }
     }
 }
@@ -77,8 +353,26 @@ pub enum IpcRequest {
         url: String,
         device: Option<String>,
     },
+    /// Absolute paths resolved by the client; each file is shared and reported separately.
+    ShareFiles {
+        paths: Vec<String>,
+        device: Option<String>,
+    },
+    ShareText {
+        text: String,
+        device: Option<String>,
+    },
     ShareClipboard {
         device: Option<String>,
+        /// Clipboard read by the client; when absent the daemon reads it itself.
+        #[serde(default)]
+        capture: Option<ClipboardCapture>,
+        /// Read the primary selection instead of the regular clipboard.
+        #[serde(default)]
+        primary: bool,
+        /// Wayland environment of the client, used for the daemon-side fallback read.
+        #[serde(default)]
+        wayland_env: Vec<(String, String)>,
     },
     Ping {
         message: Option<String>,
@@ -105,8 +399,83 @@ pub enum IpcRequest {
     },
     Media {
         device: Option<String>,
+        /// Act on the player that most recently started playing, on whichever device.
+        #[serde(default)]
+        auto_target: bool,
         action: MediaAction,
     },
+    /// Pause every player on every reachable device.
+    MediaPauseAll,
+    Notifications {
+        device: Option<String>,
+        action: NotificationAction,
+    },
+    SendSms {
+        device: Option<String>,
+        recipients: Vec<String>,
+        body: String,
+        attachments: Vec<String>,
+    },
+    /// Type into the phone's focused field through the remotekeyboard plugin.
+    SendKeys {
+        device: Option<String>,
+        keys: Vec<KeyPress>,
+    },
+    Sms {
+        device: Option<String>,
+        action: SmsAction,
+    },
+    EndCall {
+        device: Option<String>,
+    },
+    Contacts {
+        device: Option<String>,
+        action: ContactsAction,
+    },
+    ClipboardSync {
+        action: ClipboardSyncAction,
+    },
+    Transfers,
+    Queue {
+        action: QueueAction,
+    },
+    History {
+        action: HistoryAction,
+    },
+}
+
+impl IpcRequest {
+    /// The serialized `type` tag, without serializing the request.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IpcRequest::GetState => "get_state",
+            IpcRequest::ShareFile { .. } => "share_file",
+            IpcRequest::ShareUrl { .. } => "share_url",
+            IpcRequest::ShareFiles { .. } => "share_files",
+            IpcRequest::ShareText { .. } => "share_text",
+            IpcRequest::ShareClipboard { .. } => "share_clipboard",
+            IpcRequest::Ping { .. } => "ping",
+            IpcRequest::Pair { .. } => "pair",
+            IpcRequest::Unpair { .. } => "unpair",
+            IpcRequest::Find { .. } => "find",
+            IpcRequest::RefreshNetwork => "refresh_network",
+            IpcRequest::Mount { .. } => "mount",
+            IpcRequest::OpenMount { .. } => "open_mount",
+            IpcRequest::ToggleMount { .. } => "toggle_mount",
+            IpcRequest::Media { .. } => "media",
+            IpcRequest::MediaPauseAll => "media_pause_all",
+            IpcRequest::Notifications { .. } => "notifications",
+            IpcRequest::SendSms { .. } => "send_sms",
+            IpcRequest::SendKeys { .. } => "send_keys",
+            IpcRequest::Sms { .. } => "sms",
+            IpcRequest::EndCall { .. } => "end_call",
+            IpcRequest::Contacts { .. } => "contacts",
+            IpcRequest::ClipboardSync { .. } => "clipboard_sync",
+            IpcRequest::Transfers => "transfers",
+            IpcRequest::Queue { .. } => "queue",
+            IpcRequest::History { .. } => "history",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,16 +487,88 @@ pub enum MediaAction {
     Previous,
     Stop,
     Seek { ms: i32 },
+    SeekTo { position_ms: u64 },
     VolumeSet { value: u8 },
+    VolumeStep { delta: i32 },
+    MuteToggle,
+    /// Return the current state, or wait until it changes past `after`.
+    Watch { after: Option<u64> },
+    /// Every player on every reachable device.
+    ListAll,
     PlayerList,
     PlayerSet { name: String },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum NotificationAction {
+    List,
+    Dismiss { id: String },
+    Reply { id: String, message: String },
+    History {
+        app: Option<String>,
+        since: Option<DateTime<Utc>>,
+        grep: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SmsAction {
+    /// Latest message of every conversation thread.
+    List,
+    Show { thread_id: i64, limit: Option<u32> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ContactsAction {
+    Sync,
+    List,
+    Search { query: String },
+    /// Concatenated vCards of the synced address book.
+    Export,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClipboardSyncAction {
+    Pause,
+    Resume,
+    Status,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum QueueAction {
+    /// Run a share or ping request now, or hold it until its device is reachable.
+    Add { request: Box<IpcRequest> },
+    List,
+    Cancel { id: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HistoryAction {
+    /// Most recent audit entries, oldest first.
+    List {
+        device: Option<String>,
+        /// Request type such as `share_file`, or a prefix such as `share`.
+        kind: Option<String>,
+        limit: Option<usize>,
+    },
+    /// Run a logged share or ping request again.
+    Resend { id: u64 },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpcResponse {
     pub ok: bool,
     pub message: Option<String>,
     pub state: Option<DaemonState>,
+    /// Structured payload for requests that return more than a message (e.g. notification lists).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,9 +576,368 @@ pub struct WaybarPayload {
     pub text: String,
     pub tooltip: String,
     pub class: String,
+    /// Selects an entry of the module's `format-icons`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
 }
 
-pub fn runtime_socket_path() -> Result<PathBuf> {
+/// Snapshot written to the runtime state file for consumers that can only read files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateExport {
+    pub state: DaemonState,
+    pub waybar: WaybarPayload,
+}
+
+pub fn build_waybar_payload(state: &DaemonState) -> WaybarPayload {
+    let connected = state.devices.iter().filter(|d| d.reachable).count();
+    if connected == 0 {
+        return WaybarPayload {
+            text: "󰄰".to_string(),
+            tooltip: "Phone: offline".to_string(),
+            class: "disconnected".to_string(),
+            alt: None,
+        };
+    }
+
+    let device = state
+        .devices
+        .iter()
+        .find(|d| d.reachable)
+        .or_else(|| state.devices.first());
+
+    if let Some(d) = device {
+        let battery_percent = d.battery_percent;
+        let battery = battery_percent
+            .map(|v| format!("{v}%"))
+            .unwrap_or_else(|| "--".to_string());
+        let signal_icon = cellular_signal_icon(d.signal_percent);
+        let mount_suffix = if d.mounted { " 󰛳" } else { "" };
+        let charge_suffix = if d.charging == Some(true) { " " } else { "" };
+        let text = format!("{signal_icon} 󰄜{mount_suffix} {battery}{charge_suffix}");
+
+        let class = match battery_percent {
+            Some(b) if b < 30 => "crit",
+            Some(b) if b < 50 => "warn",
+            _ => "ok",
+        }
+        .to_string();
+
+        let signal_text = d
+            .signal_percent
+            .map(|v| format!("{v}%"))
+            .unwrap_or_else(|| "--".to_string());
+        let network_type = d.network_type.as_deref().unwrap_or("Unknown");
+        let mount_status = if d.mounted { "Yes" } else { "No" };
+        let mount_point = if d.mounted {
+            d.mount_point.as_deref().unwrap_or("--")
+        } else {
+            "--"
+        };
+
+        let tooltip = format!(
+            "{}\nBattery: {}\nStatus: {}\nPaired: {}\nMounted: {}\nMount point: {}\nSignal: {}\nNetwork: {}\nDevices connected: {}",
+            d.name,
+            battery,
+            if d.reachable { "Connected" } else { "Offline" },
+            if d.paired { "Yes" } else { "No" },
+            mount_status,
+            mount_point,
+            signal_text,
+            network_type,
+            connected,
+        );
+
+        return WaybarPayload {
+            text,
+            tooltip,
+            class,
+            alt: None,
+        };
+    }
+
+    WaybarPayload {
+        text: "󰄰".to_string(),
+        tooltip: "Phone: unavailable".to_string(),
+        class: "disconnected".to_string(),
+        alt: None,
+    }
+}
+
+/// Waybar payload for phone playback. `class` and `alt` are `playing`, `paused` or
+/// `stopped`; the text is `artist - title`, cut to `max_chars` characters.
+pub fn build_media_waybar_payload(media: Option<&MediaState>, max_chars: usize) -> WaybarPayload {
+    let Some(m) = media.filter(|m| m.title.is_some()) else {
+        return WaybarPayload {
+            text: String::new(),
+            tooltip: "Phone: nothing playing".to_string(),
+            class: "stopped".to_string(),
+            alt: Some("stopped".to_string()),
+        };
+    };
+
+    let title = m.title.as_deref().unwrap_or_default();
+    let text = match &m.artist {
+        Some(artist) => format!("{artist} - {title}"),
+        None => title.to_string(),
+    };
+    let status = if m.playing { "playing" } else { "paused" };
+
+    let mut tooltip = vec![title.to_string()];
+    if let Some(artist) = &m.artist {
+        tooltip.push(format!("Artist: {artist}"));
+    }
+    if let Some(album) = &m.album {
+        tooltip.push(format!("Album: {album}"));
+    }
+    match (m.position_ms, m.length_ms) {
+        (Some(pos), Some(len)) => tooltip.push(format!(
+            "Position: {} / {}",
+            format_track_time(pos),
+            format_track_time(len)
+        )),
+        (Some(pos), None) => tooltip.push(format!("Position: {}", format_track_time(pos))),
+        _ => {}
+    }
+    if let Some(player) = &m.player {
+        tooltip.push(format!("Player: {player} ({status})"));
+    }
+
+    WaybarPayload {
+        text: truncate_chars(&text, max_chars),
+        tooltip: tooltip.join("\n"),
+        class: status.to_string(),
+        alt: Some(status.to_string()),
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` for tracks an hour or longer.
+pub fn format_track_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if max_chars == 0 || text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+pub fn cellular_signal_icon(signal_percent: Option<u8>) -> &'static str {
+    match signal_percent {
+        Some(v) if v >= 75 => "󰣺",
+        Some(v) if v >= 50 => "󰣸",
+        Some(v) if v >= 30 => "󰣶",
+        Some(v) if v >= 10 => "󰣴",
+        _ => "󰣾",
+    }
+}
+
+/// Environment variables a `wl-paste` child needs to reach the compositor.
+pub const WAYLAND_ENV_VARS: &[&str] = &["WAYLAND_DISPLAY", "XDG_RUNTIME_DIR"];
+
+/// Image types accepted from the clipboard, in order of preference.
+pub const CLIPBOARD_IMAGE_TYPES: &[&str] =
+    &["image/png", "image/jpeg", "image/webp", "image/gif", "image/bmp"];
+
+/// Pick the clipboard MIME type to read from `wl-paste --list-types` output: file lists first,
+/// then images, then text.
+pub fn pick_clipboard_type(offered: &[&str]) -> Option<String> {
+    if offered.contains(&"text/uri-list") {
+        return Some("text/uri-list".to_string());
+    }
+    if let Some(image) = CLIPBOARD_IMAGE_TYPES.iter().find(|t| offered.contains(t)) {
+        return Some(image.to_string());
+    }
+    let text = ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING", "STRING", "TEXT"];
+    text.iter()
+        .find(|t| offered.contains(t))
+        .or_else(|| offered.iter().find(|t| t.starts_with("text/")))
+        .map(|t| t.to_string())
+}
+
+/// Keys with their own code in KDE Connect's remote keyboard packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecialKey {
+    Backspace = 1,
+    Tab = 2,
+    Left = 4,
+    Up = 5,
+    Right = 6,
+    Down = 7,
+    PageUp = 8,
+    PageDown = 9,
+    Home = 10,
+    End = 11,
+    Enter = 12,
+    Delete = 13,
+    Escape = 14,
+    F1 = 21,
+    F2 = 22,
+    F3 = 23,
+    F4 = 24,
+    F5 = 25,
+    F6 = 26,
+    F7 = 27,
+    F8 = 28,
+    F9 = 29,
+    F10 = 30,
+    F11 = 31,
+    F12 = 32,
+}
+
+impl SpecialKey {
+    /// The `specialKey` value sent to the phone.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let key = match name.to_ascii_lowercase().as_str() {
+            "backspace" => Self::Backspace,
+            "tab" => Self::Tab,
+            "left" => Self::Left,
+            "up" => Self::Up,
+            "right" => Self::Right,
+            "down" => Self::Down,
+            "pageup" | "page_up" | "pgup" => Self::PageUp,
+            "pagedown" | "page_down" | "pgdn" => Self::PageDown,
+            "home" => Self::Home,
+            "end" => Self::End,
+            "enter" | "return" => Self::Enter,
+            "delete" | "del" => Self::Delete,
+            "escape" | "esc" => Self::Escape,
+            "f1" => Self::F1,
+            "f2" => Self::F2,
+            "f3" => Self::F3,
+            "f4" => Self::F4,
+            "f5" => Self::F5,
+            "f6" => Self::F6,
+            "f7" => Self::F7,
+            "f8" => Self::F8,
+            "f9" => Self::F9,
+            "f10" => Self::F10,
+            "f11" => Self::F11,
+            "f12" => Self::F12,
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+/// One remote keyboard event: `text` to type, or a `special` key, with optional modifiers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyPress {
+    /// Ignored when `special` is set.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub special: Option<SpecialKey>,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl KeyPress {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn special(key: SpecialKey) -> Self {
+        Self {
+            special: Some(key),
+            ..Self::default()
+        }
+    }
+
+    /// Parse a key combo such as `enter`, `ctrl+a`, `ctrl+shift+tab` or `alt+f4`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+        // `+` and `ctrl++` name the plus key itself.
+        if spec == "+" || spec.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let key = parts
+            .pop()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| anyhow!("empty key in '{spec}'"))?;
+
+        let mut press = match SpecialKey::from_name(key) {
+            Some(special) => Self::special(special),
+            None if key.chars().count() == 1 => Self::text(key),
+            None if key.eq_ignore_ascii_case("space") => Self::text(" "),
+            None => return Err(anyhow!("unknown key '{key}' in '{spec}'")),
+        };
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => press.ctrl = true,
+                "shift" => press.shift = true,
+                "alt" => press.alt = true,
+                other => return Err(anyhow!("unknown modifier '{other}' in '{spec}'")),
+            }
+        }
+        Ok(press)
+    }
+}
+
+/// Whether `text` is a single link the phone should open rather than plain text to share.
+pub fn looks_like_url(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty()
+        && !text.contains(char::is_whitespace)
+        && ["http://", "https://", "ftp://", "mailto:", "tel:", "geo:", "sms:"]
+            .iter()
+            .any(|scheme| text.starts_with(scheme))
+}
+
+pub fn runtime_dir() -> PathBuf {
     let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
-    Ok(PathBuf::from(runtime_dir).join("hyprconnect.sock"))
+    PathBuf::from(runtime_dir)
+}
+
+pub fn runtime_socket_path() -> Result<PathBuf> {
+    Ok(runtime_dir().join("hyprconnect.sock"))
+}
+
+/// Persistent per-user state directory (`$XDG_STATE_HOME/hyprconnect`).
+pub fn state_dir() -> Result<PathBuf> {
+    let base = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("unable to resolve XDG state dir")?;
+    Ok(base.join("hyprconnect"))
+}
+
+/// User data directory (`$XDG_DATA_HOME`), shared with kdeconnectd's own caches.
+pub fn user_data_dir() -> Result<PathBuf> {
+    dirs::data_dir().context("unable to resolve XDG data dir")
+}
+
+/// Expand a leading `~` in a configured path.
+pub fn expand_home(path: &str) -> Result<PathBuf> {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        _ => return Ok(PathBuf::from(path)),
+    };
+    let home = dirs::home_dir().context("unable to resolve home directory")?;
+    Ok(home.join(rest))
+}
+
+/// Directory holding exported state files (`state.json`, `state.txt`).
+pub fn state_export_dir() -> PathBuf {
+    runtime_dir().join("hyprconnect")
 }
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, Weekday};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// App name used when Hyprconnect's own connection/battery notifications go through the rules.
+pub const HYPRCONNECT_APP: &str = "Hyprconnect";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    Suppress,
+    Low,
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuietAction {
+    #[default]
+    Suppress,
+    Downgrade,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct NotificationRules {
+    /// When non-empty, only these apps are mirrored (case-insensitive).
+    pub app_allowlist: Vec<String>,
+    /// Apps that are never mirrored (case-insensitive).
+    pub app_denylist: Vec<String>,
+    /// Regex filters, evaluated in order; the first match wins.
+    pub filters: Vec<NotificationFilter>,
+    /// Urgency override per app name (case-insensitive).
+    pub app_urgency: Vec<AppUrgency>,
+    pub quiet_hours: Vec<QuietHours>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct NotificationFilter {
+    pub name: Option<String>,
+    pub app: Option<FilterPattern>,
+    pub title: Option<FilterPattern>,
+    pub body: Option<FilterPattern>,
+    pub action: RuleAction,
+}
+
+/// Case-insensitive regex compiled when the config is loaded, so an invalid pattern is a
+/// config error rather than a filter that never matches.
+#[derive(Debug, Clone)]
+pub struct FilterPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl FilterPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern).case_insensitive(true).build()?;
+        Ok(Self {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+impl Serialize for FilterPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for FilterPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppUrgency {
+    pub app: String,
+    pub urgency: NotificationUrgency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Local start time, `HH:MM`.
+    pub start: ClockTime,
+    /// Local end time, `HH:MM`. May be earlier than `start` to wrap past midnight.
+    pub end: ClockTime,
+    /// Optional weekday filter (`mon`..`sun`) applied to the day the window starts.
+    #[serde(default)]
+    pub days: Vec<DayName>,
+    #[serde(default)]
+    pub action: QuietAction,
+}
+
+/// Local `HH:MM` time, parsed when the config is loaded like [`FilterPattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTime(pub NaiveTime);
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%H:%M"))
+    }
+}
+
+impl Serialize for ClockTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClockTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(raw.trim(), "%H:%M")
+            .map(Self)
+            .map_err(|_| serde::de::Error::custom(format!("invalid time '{raw}', expected HH:MM")))
+    }
+}
+
+/// Weekday written as `mon`..`sun` (full names are accepted too).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayName(pub Weekday);
+
+impl Serialize for DayName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string().to_lowercase())
+    }
+}
+
+impl<'de> Deserialize<'de> for DayName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.trim()
+            .parse::<Weekday>()
+            .map(Self)
+            .map_err(|_| serde::de::Error::custom(format!("invalid day '{raw}', expected mon..sun")))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDecision {
+    pub show: bool,
+    pub urgency: NotificationUrgency,
+    /// Human-readable trace of the rules that influenced the decision.
+    pub matched: Vec<String>,
+}
+
+impl NotificationRules {
+    /// Decide whether a notification is shown and at which urgency, starting from `urgency`.
+    pub fn evaluate(
+        &self,
+        app: &str,
+        title: &str,
+        body: &str,
+        urgency: NotificationUrgency,
+        now: DateTime<Local>,
+    ) -> RuleDecision {
+        let mut decision = RuleDecision {
+            show: true,
+            urgency,
+            matched: Vec::new(),
+        };
+
+        if self.app_denylist.iter().any(|a| a.eq_ignore_ascii_case(app)) {
+            decision.show = false;
+            decision.matched.push(format!("app_denylist: {app}"));
+            return decision;
+        }
+
+        // Hyprconnect's own notifications are not subject to the phone app allowlist.
+        if !self.app_allowlist.is_empty()
+            && !app.eq_ignore_ascii_case(HYPRCONNECT_APP)
+            && !self.app_allowlist.iter().any(|a| a.eq_ignore_ascii_case(app))
+        {
+            decision.show = false;
+            decision.matched.push(format!("app_allowlist: {app} not listed"));
+            return decision;
+        }
+
+        if let Some(rule) = self.app_urgency.iter().find(|r| r.app.eq_ignore_ascii_case(app)) {
+            decision.urgency = rule.urgency;
+            decision.matched.push(format!("app_urgency: {app} -> {:?}", rule.urgency));
+        }
+
+        for (idx, filter) in self.filters.iter().enumerate() {
+            let label = filter
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("filters[{idx}]"));
+            if !filter.matches(app, title, body) {
+                continue;
+            }
+            decision.matched.push(format!("{label}: {:?}", filter.action));
+            match filter.action {
+                RuleAction::Suppress => {
+                    decision.show = false;
+                    return decision;
+                }
+                RuleAction::Low => decision.urgency = NotificationUrgency::Low,
+                RuleAction::Normal => decision.urgency = NotificationUrgency::Normal,
+                RuleAction::Critical => decision.urgency = NotificationUrgency::Critical,
+            }
+            break;
+        }
+
+        // Critical notifications always get through quiet hours.
+        if decision.urgency == NotificationUrgency::Critical {
+            return decision;
+        }
+
+        if let Some(window) = self.quiet_hours.iter().find(|q| q.contains(now)) {
+            decision
+                .matched
+                .push(format!("quiet_hours {}-{}: {:?}", window.start, window.end, window.action));
+            match window.action {
+                QuietAction::Suppress => decision.show = false,
+                QuietAction::Downgrade => decision.urgency = NotificationUrgency::Low,
+            }
+        }
+
+        decision
+    }
+}
+
+impl NotificationFilter {
+    fn matches(&self, app: &str, title: &str, body: &str) -> bool {
+        let fields = [(&self.app, app), (&self.title, title), (&self.body, body)];
+        if fields.iter().all(|(pattern, _)| pattern.is_none()) {
+            return false;
+        }
+        fields
+            .iter()
+            .all(|(pattern, value)| pattern.as_ref().is_none_or(|p| p.is_match(value)))
+    }
+}
+
+impl QuietHours {
+    pub fn contains(&self, now: DateTime<Local>) -> bool {
+        let (start, end) = (self.start.0, self.end.0);
+        let time = now.time();
+        let today = now.weekday();
+
+        if start <= end {
+            return start <= time && time < end && self.day_matches(today);
+        }
+        // Window wraps past midnight: the evening part belongs to today, the
+        // morning part to the window that started yesterday.
+        if time >= start {
+            return self.day_matches(today);
+        }
+        time < end && self.day_matches(today.pred())
+    }
+
+    fn day_matches(&self, day: Weekday) -> bool {
+        if self.days.is_empty() {
+            return true;
+        }
+        self.days.iter().any(|d| d.0 == day)
+    }
+}
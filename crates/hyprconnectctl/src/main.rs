@@ -1,10 +1,17 @@
 use anyhow::{anyhow, Context, Result};
-use clap::{CommandFactory, Parser, Subcommand};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeDelta, Utc};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
 use hyprconnect_core::{
-    runtime_socket_path, DaemonState, IpcRequest, IpcResponse, MediaAction, WaybarPayload,
+    build_media_waybar_payload, build_waybar_payload, format_track_time, looks_like_url,
+    pick_clipboard_type, runtime_socket_path, state_export_dir, AuditEntry, ClipboardCapture,
+    ClipboardSyncAction, Config, Contact, ContactsAction, DaemonState, FileShareResult,
+    HistoryAction, IpcRequest, IpcResponse, KeyPress, MediaAction, MediaPlayerEntry, MediaState,
+    MediaUpdate, NotificationAction, NotificationRecord, NotificationUrgency, PhoneNotification,
+    QueueAction, QueuedAction, SmsAction, SmsMessage, SpecialKey, Transfer, TransferState,
+    WAYLAND_ENV_VARS,
 };
-use std::io;
+use std::io::{self, IsTerminal, Read};
 use std::process::Stdio;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::UnixStream;
@@ -141,21 +148,49 @@ enum Commands {
     )]
     WaybarJson,
     #[command(
-        about = "Share a file to a device",
-        long_about = "Send a local file path to a paired and reachable device using KDE Connect share plugin."
+        about = "Emit Waybar JSON payload for phone media",
+        long_about = "Output a JSON object for a Waybar custom module showing phone playback.\nThe payload contains text, tooltip, class (playing, paused or stopped) and alt fields.\nWith --follow, a new line is printed whenever the daemon sees the playback state change; set `return-type = \"json\"` and omit `interval` in the module config."
+    )]
+    WaybarMediaJson {
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
+        #[arg(long, help = "Keep running and print a payload on every change")]
+        follow: bool,
+    },
+    #[command(
+        about = "Share files to a device",
+        long_about = "Send one or more local files to a paired and reachable device using KDE Connect share plugin.\nPaths are resolved against the current directory and checked for readability before sending; quoted globs (`'*.jpg'`) are expanded by hyprconnectctl. Directories are expanded to the files they contain, or archived to a temporary zip with --dirs zip."
     )]
     ShareFile {
         #[arg(
-            help = "Path to a file to share",
-            long_help = "Path to a local file that will be sent through KDE Connect share plugin."
+            required = true,
+            help = "Files, directories or globs to share",
+            long_help = "Local files, directories or glob patterns (`*`, `?` in the last path component) that will be sent through KDE Connect share plugin."
+        )]
+        paths: Vec<String>,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = DirMode::Expand,
+            help = "How to share directories: expand to their files or zip them"
         )]
-        path: String,
+        dirs: DirMode,
         #[arg(
             long,
             help = "Target device id",
             long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
         )]
         device: Option<String>,
+        #[arg(
+            long,
+            help = "Queue until the device is reachable",
+            long_help = "When the device is offline, hyprconnectd stores the request and sends it as soon as the device connects (see `hyprconnectctl queue list`). Offline devices must be paired; pass --device when several are."
+        )]
+        queue: bool,
     },
     #[command(
         about = "Share a URL to a device",
@@ -173,10 +208,83 @@ enum Commands {
             long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
         )]
         device: Option<String>,
+        #[arg(
+            long,
+            help = "Queue until the device is reachable",
+            long_help = "When the device is offline, hyprconnectd stores the request and sends it as soon as the device connects (see `hyprconnectctl queue list`). Offline devices must be paired; pass --device when several are."
+        )]
+        queue: bool,
+    },
+    #[command(
+        about = "Share plain text to a device",
+        long_about = "Send text through the KDE Connect share plugin's shareText, which lands in the phone's share sheet or clipboard instead of being opened as a URL. Reads stdin when TEXT is omitted or '-'."
+    )]
+    ShareText {
+        #[arg(help = "Text to share; read from stdin when omitted or '-'")]
+        text: Option<String>,
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
+        #[arg(
+            long,
+            help = "Queue until the device is reachable",
+            long_help = "When the device is offline, hyprconnectd stores the request and sends it as soon as the device connects (see `hyprconnectctl queue list`). Offline devices must be paired; pass --device when several are."
+        )]
+        queue: bool,
+    },
+    #[command(
+        about = "Share a file, URL or text, detected from the argument",
+        long_about = "Share ARG as a file when it names an existing path (directories are expanded to their files, as with share-file), as a URL when it starts with a known scheme (http, https, mailto, tel, ...), and as plain text otherwise. '-' reads text from stdin."
+    )]
+    Send {
+        #[arg(help = "File path, URL or text; '-' reads stdin")]
+        arg: String,
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
+        #[arg(
+            long,
+            help = "Queue until the device is reachable",
+            long_help = "When the device is offline, hyprconnectd stores the request and sends it as soon as the device connects (see `hyprconnectctl queue list`). Offline devices must be paired; pass --device when several are."
+        )]
+        queue: bool,
+    },
+    #[command(
+        about = "Type text and keys on the phone",
+        long_about = "Send text and key presses to the focused field on the phone through the KDE Connect remotekeyboard plugin. The phone's KDE Connect keyboard must be the active input method.\nText is typed first (newlines become Enter), then each --key in order. Keys are named (enter, tab, backspace, delete, escape, left, right, up, down, home, end, pageup, pagedown, f1-f12, space) or single characters, optionally with ctrl+, shift+ and alt+ prefixes.\nWith --interactive, keystrokes are streamed from the terminal until Ctrl-] is pressed."
+    )]
+    Type {
+        #[arg(help = "Text to type; '-' or no text and no --key reads stdin")]
+        text: Option<String>,
+        #[arg(
+            long = "key",
+            value_name = "KEY",
+            help = "Key or combo to press after the text, e.g. enter, ctrl+a, shift+tab (repeatable)"
+        )]
+        keys: Vec<String>,
+        #[arg(
+            long,
+            short,
+            conflicts_with_all = ["text", "keys"],
+            help = "Stream keystrokes from the terminal until Ctrl-]"
+        )]
+        interactive: bool,
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
     },
     #[command(
-        about = "Share clipboard text or URL",
-        long_about = "Read current Wayland clipboard contents via wl-paste and share them to a device through KDE Connect."
+        about = "Share clipboard text, URL, image or files",
+        long_about = "Read current Wayland clipboard contents via wl-paste and share them to a device through KDE Connect. Copied files are shared as files, images as an image file, URLs as links and anything else as text.\nThe clipboard is read by hyprconnectctl, so the daemon does not need access to the Wayland session."
     )]
     ShareClipboard {
         #[arg(
@@ -185,6 +293,8 @@ enum Commands {
             long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
         )]
         device: Option<String>,
+        #[arg(long, help = "Share the primary selection (middle-click buffer) instead")]
+        primary: bool,
     },
     #[command(
         about = "Ping a device",
@@ -203,6 +313,12 @@ enum Commands {
             long_help = "Optional message displayed by KDE Connect on the target device."
         )]
         message: Option<String>,
+        #[arg(
+            long,
+            help = "Queue until the device is reachable",
+            long_help = "When the device is offline, hyprconnectd stores the request and sends it as soon as the device connects (see `hyprconnectctl queue list`). Offline devices must be paired; pass --device when several are."
+        )]
+        queue: bool,
     },
     #[command(
         about = "Control phone media playback",
@@ -215,9 +331,118 @@ enum Commands {
             long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
         )]
         device: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            conflicts_with = "device",
+            help = "Pick the device and player automatically",
+            long_help = "With `auto`, act on the player that most recently started playing, on whichever reachable device it is. Falls back to the usual device choice when nothing has played yet."
+        )]
+        target: Option<MediaTarget>,
         #[command(subcommand)]
         command: MediaCommands,
     },
+    #[command(
+        about = "List and act on phone notifications",
+        long_about = "List notifications currently shown on the phone, dismiss them, or reply to messaging notifications through KDE Connect notifications plugin."
+    )]
+    Notifications {
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
+        #[command(subcommand)]
+        command: NotificationCommands,
+    },
+    #[command(
+        about = "Send SMS through the phone",
+        long_about = "Send text messages through the phone using KDE Connect sms plugin. The plugin must be enabled on the phone."
+    )]
+    Sms {
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. If omitted, hyprconnect chooses default_device, then first paired+reachable device."
+        )]
+        device: Option<String>,
+        #[command(subcommand)]
+        command: SmsCommands,
+    },
+    #[command(
+        about = "Manage phone call state",
+        long_about = "Inspect or adjust the call state hyprconnectd tracks from KDE Connect telephony events."
+    )]
+    Call {
+        #[command(subcommand)]
+        command: CallCommands,
+    },
+    #[command(
+        about = "Browse contacts synced from the phone",
+        long_about = "Sync the phone's address book through the KDE Connect contacts plugin and query the local copy. Synced contacts are used to show names instead of numbers for SMS, calls and notifications."
+    )]
+    Contacts {
+        #[arg(
+            long,
+            help = "Target device id",
+            long_help = "Optional device id override. sync falls back to default_device, then first paired+reachable device; list, search and export cover every synced device when omitted."
+        )]
+        device: Option<String>,
+        #[command(subcommand)]
+        command: ContactsCommands,
+    },
+    #[command(
+        about = "Show file transfers",
+        long_about = "List KDE Connect file transfers with progress, speed and result. hyprconnectd records them by serving the KDE job progress interface (org.kde.JobViewServer); under Plasma, plasmashell owns that interface and no transfers are recorded."
+    )]
+    Transfers {
+        #[arg(long, help = "Keep running and print progress as it changes")]
+        follow: bool,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(
+        about = "Show the log of actions handled by the daemon",
+        long_about = "Print hyprconnectd's audit log ($XDG_STATE_HOME/hyprconnect/audit.jsonl): shares, pings, SMS, media and other actions with target device, summary, result and time. Lookups such as status or list commands are not logged."
+    )]
+    History {
+        #[arg(long, help = "Only entries for this device id")]
+        device: Option<String>,
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            help = "Only entries of this request type (e.g. share_file, ping) or prefix (e.g. share)"
+        )]
+        kind: Option<String>,
+        #[arg(long, default_value_t = 50, help = "Show at most this many recent entries")]
+        limit: usize,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+        #[arg(
+            long,
+            value_name = "ID",
+            conflicts_with_all = ["device", "kind", "json"],
+            help = "Send a logged share or ping again to the same device"
+        )]
+        resend: Option<u64>,
+    },
+    #[command(
+        about = "Inspect or cancel queued actions",
+        long_about = "List or cancel share and ping requests queued with --queue. hyprconnectd sends them when their device becomes reachable and drops them after queue_expiry_hours."
+    )]
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommands,
+    },
+    #[command(
+        about = "Control automatic clipboard sync",
+        long_about = "Pause, resume or inspect the daemon's clipboard sync (clipboard_sync_enabled in config.toml). Pausing is useful before copying something you do not want on the phone."
+    )]
+    ClipboardSync {
+        #[command(subcommand)]
+        command: ClipboardSyncCommands,
+    },
     #[command(
         about = "Generate shell completion script",
         long_about = "Print shell completion script to stdout for a chosen shell.\nUse with redirection to install completion files."
@@ -235,7 +460,10 @@ enum Commands {
 #[derive(Debug, Subcommand)]
 enum MediaCommands {
     #[command(about = "Show phone media status")]
-    Status,
+    Status {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
     #[command(about = "Toggle play/pause")]
     PlayPause,
     #[command(about = "Skip to next track")]
@@ -246,14 +474,30 @@ enum MediaCommands {
     Stop,
     #[command(about = "Seek by milliseconds (negative allowed)")]
     Seek {
-        #[arg(long, help = "Seek delta in milliseconds")]
+        #[arg(long, allow_negative_numbers = true, help = "Seek delta in milliseconds")]
         ms: i32,
     },
+    #[command(about = "Seek to an absolute position in the current track")]
+    SeekTo {
+        #[arg(long, help = "Position in milliseconds from the start of the track")]
+        ms: u64,
+    },
     #[command(about = "Set phone media volume (0-100)")]
     Volume {
         #[arg(long, help = "Absolute volume percent", value_parser = clap::value_parser!(u8).range(0..=100))]
         set: u8,
     },
+    #[command(about = "Raise or lower phone media volume, clamped to 0-100")]
+    VolumeStep {
+        #[arg(
+            long,
+            allow_negative_numbers = true,
+            help = "Volume change in percent (negative lowers)"
+        )]
+        delta: i32,
+    },
+    #[command(about = "Mute phone media, or restore the volume from before muting")]
+    MuteToggle,
     #[command(about = "List available phone media players")]
     PlayerList,
     #[command(about = "Set active phone media player")]
@@ -261,6 +505,181 @@ enum MediaCommands {
         #[arg(long, help = "Player name as listed by player-list")]
         name: String,
     },
+    #[command(
+        about = "List every player on every reachable device with its state",
+        long_about = "List every player on every reachable device with its state. Covers all devices, so --device and --target are rejected."
+    )]
+    ListAll {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(
+        about = "Pause every player on every reachable device",
+        long_about = "Pause every player on every reachable device. Covers all devices, so --device and --target are rejected."
+    )]
+    PauseAll,
+}
+
+#[derive(Debug, Subcommand)]
+enum NotificationCommands {
+    #[command(about = "List active phone notifications")]
+    List {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Dismiss a phone notification")]
+    Dismiss {
+        #[arg(help = "Notification id as shown by list")]
+        id: String,
+    },
+    #[command(about = "Reply to a repliable phone notification")]
+    Reply {
+        #[arg(help = "Notification id as shown by list")]
+        id: String,
+        #[arg(help = "Reply text")]
+        message: String,
+    },
+    #[command(
+        about = "Search the persistent phone notification history",
+        long_about = "Show phone notifications recorded by hyprconnectd, including dismissed ones. Without --device, history of all devices is searched."
+    )]
+    History {
+        #[arg(long, help = "Only show notifications from this app")]
+        app: Option<String>,
+        #[arg(
+            long,
+            help = "Only show notifications newer than this",
+            long_help = "Relative age such as 30m, 2h or 7d, a date (YYYY-MM-DD), or an RFC 3339 timestamp."
+        )]
+        since: Option<String>,
+        #[arg(long, help = "Case-insensitive regex matched against title and text")]
+        grep: Option<String>,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(
+        about = "Show which notification rule matches a sample notification",
+        long_about = "Evaluate notification_rules from the local config against a sample notification and print the decision. Does not contact the daemon."
+    )]
+    Test {
+        #[arg(long, help = "App name of the sample notification")]
+        app: String,
+        #[arg(long, default_value = "", help = "Title of the sample notification")]
+        title: String,
+        #[arg(long, default_value = "", help = "Body text of the sample notification")]
+        body: String,
+        #[arg(long, help = "Evaluate as if it were this local time (HH:MM)")]
+        at: Option<String>,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SmsCommands {
+    #[command(
+        about = "Send an SMS",
+        long_about = "Send an SMS to one or more recipients. The message is taken from --message, or read from stdin when omitted."
+    )]
+    Send {
+        #[arg(
+            long,
+            required = true,
+            help = "Recipient phone number or contact name (repeatable)",
+            long_help = "Recipient phone number, or a contact name looked up in the synced address book (see `contacts sync`). A name must match exactly one contact with a phone number. Repeatable."
+        )]
+        to: Vec<String>,
+        #[arg(long, help = "Message text; read from stdin when omitted")]
+        message: Option<String>,
+        #[arg(long, help = "File to attach as MMS (repeatable)")]
+        attach: Vec<String>,
+    },
+    #[command(about = "List SMS conversation threads, newest first")]
+    List {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Show messages of an SMS conversation thread")]
+    Show {
+        #[arg(help = "Thread id as shown by list")]
+        thread: i64,
+        #[arg(long, help = "Only fetch the most recent N messages")]
+        limit: Option<u32>,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CallCommands {
+    #[command(
+        about = "Mark the current call as ended",
+        long_about = "KDE Connect does not signal the end of an answered call over D-Bus. Use this to clear in_call and resume local media paused for the call."
+    )]
+    End {
+        #[arg(long, help = "Only end the call on this device id")]
+        device: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MediaTarget {
+    /// The player that most recently started playing.
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DirMode {
+    /// Share every file inside the directory, recursively.
+    Expand,
+    /// Share the directory as one temporary zip archive.
+    Zip,
+}
+
+#[derive(Debug, Subcommand)]
+enum ClipboardSyncCommands {
+    #[command(about = "Stop pushing clipboard changes to the phone until resumed")]
+    Pause,
+    #[command(about = "Resume pushing clipboard changes to the phone")]
+    Resume,
+    #[command(about = "Show whether clipboard sync is running or paused")]
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+enum QueueCommands {
+    #[command(about = "List queued actions")]
+    List {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Remove a queued action")]
+    Cancel {
+        #[arg(help = "Queue id from `hyprconnectctl queue list`")]
+        id: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ContactsCommands {
+    #[command(about = "Fetch the address book from the phone now")]
+    Sync,
+    #[command(about = "List synced contacts")]
+    List {
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Search synced contacts by name, number or email")]
+    Search {
+        query: String,
+        #[arg(long, help = "Emit structured JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Export synced contacts as vCard")]
+    Export {
+        #[arg(long, help = "Write to this file instead of stdout")]
+        output: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -333,31 +752,525 @@ async fn main() -> Result<()> {
             let payload = build_waybar_payload(&state);
             println!("{}", serde_json::to_string(&payload)?);
         }
-        Commands::ShareFile { path, device } => {
-            print_message(send(IpcRequest::ShareFile { path, device }).await?);
+        Commands::WaybarMediaJson { device, follow } => {
+            let max_chars = Config::load()?.media_waybar_max_chars;
+            let mut after = None;
+            let mut last = None;
+            loop {
+                let action = MediaAction::Watch { after };
+                let request = IpcRequest::Media {
+                    device: device.clone(),
+                    auto_target: false,
+                    action,
+                };
+                let update = match send(request).await {
+                    Ok(resp) => response_data::<MediaUpdate>(resp),
+                    Err(err) => Err(err),
+                };
+                let payload = match &update {
+                    Ok(update) => build_media_waybar_payload(update.state.as_ref(), max_chars),
+                    Err(_) if follow => build_media_waybar_payload(None, max_chars),
+                    Err(err) => return Err(anyhow!("{err:#}")),
+                };
+                let line = serde_json::to_string(&payload)?;
+                if last.as_ref() != Some(&line) {
+                    println!("{line}");
+                    last = Some(line);
+                }
+                if !follow {
+                    break;
+                }
+                match update {
+                    Ok(update) => after = Some(update.generation),
+                    Err(_) => {
+                        // The daemon is down or restarting; retry without a stale generation.
+                        after = None;
+                        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    }
+                }
+            }
         }
-        Commands::ShareUrl { url, device } => {
-            print_message(send(IpcRequest::ShareUrl { url, device }).await?);
+        Commands::ShareFile {
+            paths,
+            dirs,
+            device,
+            queue,
+        } => {
+            let paths = resolve_share_paths(&paths, dirs).await?;
+            share_files(paths, device, queue).await?;
         }
-        Commands::ShareClipboard { device } => {
-            print_message(send(IpcRequest::ShareClipboard { device }).await?);
+        Commands::ShareUrl { url, device, queue } => {
+            print_message(send(queued(IpcRequest::ShareUrl { url, device }, queue)).await?);
         }
-        Commands::Ping { device, message } => {
-            print_message(send(IpcRequest::Ping { message, device }).await?);
+        Commands::ShareText {
+            text,
+            device,
+            queue,
+        } => {
+            let text = match text.as_deref() {
+                None | Some("-") => read_stdin()?,
+                Some(text) => text.to_string(),
+            };
+            print_message(send(queued(IpcRequest::ShareText { text, device }, queue)).await?);
         }
-        Commands::Media { device, command } => {
+        Commands::Type {
+            text,
+            keys,
+            interactive,
+            device,
+        } => {
+            if interactive {
+                stream_keys(device).await?;
+            } else {
+                let specs = keys
+                    .iter()
+                    .map(|spec| KeyPress::parse(spec))
+                    .collect::<Result<Vec<_>>>()?;
+                let mut presses = match text.as_deref() {
+                    Some("-") => text_key_presses(&read_stdin()?),
+                    Some(text) => text_key_presses(text),
+                    None if specs.is_empty() => text_key_presses(&read_stdin()?),
+                    None => Vec::new(),
+                };
+                presses.extend(specs);
+                print_message(
+                    send(IpcRequest::SendKeys {
+                        device,
+                        keys: presses,
+                    })
+                    .await?,
+                );
+            }
+        }
+        Commands::Send { arg, device, queue } => {
+            if arg != "-" && std::path::Path::new(&arg).exists() {
+                let paths = resolve_share_paths(&[arg], DirMode::Expand).await?;
+                share_files(paths, device, queue).await?;
+                return Ok(());
+            }
+            let request = if arg == "-" {
+                IpcRequest::ShareText {
+                    text: read_stdin()?,
+                    device,
+                }
+            } else if looks_like_url(&arg) {
+                IpcRequest::ShareUrl { url: arg, device }
+            } else {
+                IpcRequest::ShareText { text: arg, device }
+            };
+            print_message(send(queued(request, queue)).await?);
+        }
+        Commands::ShareClipboard { device, primary } => {
+            let capture = capture_clipboard(primary).await;
+            let wayland_env = WAYLAND_ENV_VARS
+                .iter()
+                .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_string(), v)))
+                .collect();
+            print_message(
+                send(IpcRequest::ShareClipboard {
+                    device,
+                    capture,
+                    primary,
+                    wayland_env,
+                })
+                .await?,
+            );
+        }
+        Commands::Ping {
+            device,
+            message,
+            queue,
+        } => {
+            print_message(send(queued(IpcRequest::Ping { message, device }, queue)).await?);
+        }
+        Commands::Media {
+            device,
+            target,
+            command: MediaCommands::Status { json },
+        } => {
+            let resp = send(IpcRequest::Media {
+                device,
+                auto_target: target == Some(MediaTarget::Auto),
+                action: MediaAction::Status,
+            })
+            .await?;
+            let state: MediaState = response_data(resp)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&state)?);
+            } else {
+                println!("{}", format_media_state(&state));
+            }
+        }
+        Commands::Media {
+            device,
+            target,
+            command: MediaCommands::ListAll { json },
+        } => {
+            reject_media_target("list-all", device.as_deref(), target)?;
+            let resp = send(IpcRequest::Media {
+                device: None,
+                auto_target: false,
+                action: MediaAction::ListAll,
+            })
+            .await?;
+            let players: Vec<MediaPlayerEntry> = response_data(resp)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&players)?);
+            } else if players.is_empty() {
+                println!("No phone media players reported");
+            } else {
+                for entry in &players {
+                    println!("{}", format_player_entry(entry));
+                }
+            }
+        }
+        Commands::Media {
+            device,
+            target,
+            command: MediaCommands::PauseAll,
+        } => {
+            reject_media_target("pause-all", device.as_deref(), target)?;
+            print_message(send(IpcRequest::MediaPauseAll).await?);
+        }
+        Commands::Media {
+            device,
+            target,
+            command,
+        } => {
             let action = match command {
-                MediaCommands::Status => MediaAction::Status,
+                MediaCommands::Status { .. } => MediaAction::Status,
                 MediaCommands::PlayPause => MediaAction::PlayPause,
                 MediaCommands::Next => MediaAction::Next,
                 MediaCommands::Previous => MediaAction::Previous,
                 MediaCommands::Stop => MediaAction::Stop,
                 MediaCommands::Seek { ms } => MediaAction::Seek { ms },
+                MediaCommands::SeekTo { ms } => MediaAction::SeekTo { position_ms: ms },
                 MediaCommands::Volume { set } => MediaAction::VolumeSet { value: set },
+                MediaCommands::VolumeStep { delta } => MediaAction::VolumeStep { delta },
+                MediaCommands::MuteToggle => MediaAction::MuteToggle,
                 MediaCommands::PlayerList => MediaAction::PlayerList,
                 MediaCommands::PlayerSet { name } => MediaAction::PlayerSet { name },
+                MediaCommands::ListAll { .. } | MediaCommands::PauseAll => {
+                    unreachable!("handled above")
+                }
+            };
+            print_message(
+                send(IpcRequest::Media {
+                    device,
+                    auto_target: target == Some(MediaTarget::Auto),
+                    action,
+                })
+                .await?,
+            );
+        }
+        Commands::Notifications { device, command } => match command {
+            NotificationCommands::List { json } => {
+                let resp = send(IpcRequest::Notifications {
+                    device,
+                    action: NotificationAction::List,
+                })
+                .await?;
+                let notifications: Vec<PhoneNotification> = response_data(resp)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&notifications)?);
+                } else if notifications.is_empty() {
+                    println!("No active phone notifications");
+                } else {
+                    for n in &notifications {
+                        let reply = if n.reply_id.is_some() { " [reply]" } else { "" };
+                        println!("{} [{}] {}: {}{}", n.id, n.app_name, n.title, n.text, reply);
+                    }
+                }
+            }
+            NotificationCommands::Dismiss { id } => {
+                let action = NotificationAction::Dismiss { id };
+                print_message(send(IpcRequest::Notifications { device, action }).await?);
+            }
+            NotificationCommands::Reply { id, message } => {
+                let action = NotificationAction::Reply { id, message };
+                print_message(send(IpcRequest::Notifications { device, action }).await?);
+            }
+            NotificationCommands::History {
+                app,
+                since,
+                grep,
+                json,
+            } => {
+                let since = since.as_deref().map(parse_since).transpose()?;
+                let action = NotificationAction::History { app, since, grep };
+                let resp = send(IpcRequest::Notifications { device, action }).await?;
+                let records: Vec<NotificationRecord> = response_data(resp)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&records)?);
+                } else if records.is_empty() {
+                    println!("No matching notifications in history");
+                } else {
+                    for r in &records {
+                        let when = r.posted_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                        let dismissed = if r.dismissed { " (dismissed)" } else { "" };
+                        println!("{when} [{}] {}: {}{dismissed}", r.app_name, r.title, r.text);
+                    }
+                }
+            }
+            NotificationCommands::Test {
+                app,
+                title,
+                body,
+                at,
+                json,
+            } => {
+                let config = Config::load()?;
+                let now = match at {
+                    Some(at) => {
+                        let time = NaiveTime::parse_from_str(&at, "%H:%M")
+                            .with_context(|| format!("invalid --at time '{at}', expected HH:MM"))?;
+                        Local::now()
+                            .date_naive()
+                            .and_time(time)
+                            .and_local_timezone(Local)
+                            .earliest()
+                            .ok_or_else(|| anyhow!("--at time '{at}' does not exist today"))?
+                    }
+                    None => Local::now(),
+                };
+                let decision = config.notification_rules.evaluate(
+                    &app,
+                    &title,
+                    &body,
+                    NotificationUrgency::Normal,
+                    now,
+                );
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&decision)?);
+                } else {
+                    let verdict = if decision.show { "shown" } else { "suppressed" };
+                    println!("Decision: {verdict} (urgency {:?})", decision.urgency);
+                    if decision.matched.is_empty() {
+                        println!("Matched rules: none (default)");
+                    } else {
+                        println!("Matched rules:");
+                        for rule in &decision.matched {
+                            println!("- {rule}");
+                        }
+                    }
+                }
+            }
+        },
+        Commands::Sms { device, command } => match command {
+            SmsCommands::Send {
+                to,
+                message,
+                attach,
+            } => {
+                let body = match message {
+                    Some(message) => message,
+                    None if attach.is_empty() || !io::stdin().is_terminal() => read_stdin()?,
+                    None => String::new(),
+                };
+                let attachments = attach
+                    .iter()
+                    .map(|p| {
+                        std::fs::canonicalize(p)
+                            .map(|p| p.to_string_lossy().to_string())
+                            .with_context(|| format!("attachment not found: {p}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                print_message(
+                    send(IpcRequest::SendSms {
+                        device,
+                        recipients: to,
+                        body,
+                        attachments,
+                    })
+                    .await?,
+                );
+            }
+            SmsCommands::List { json } => {
+                let action = SmsAction::List;
+                let threads: Vec<SmsMessage> =
+                    response_data(send(IpcRequest::Sms { device, action }).await?)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&threads)?);
+                } else if threads.is_empty() {
+                    println!("No SMS conversations found");
+                } else {
+                    for m in &threads {
+                        let when = m.date.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                        let unread = if m.read { "" } else { " *" };
+                        let preview: String = m.body.lines().next().unwrap_or("").chars().take(60).collect();
+                        println!(
+                            "{} {when} {}{unread}: {preview}",
+                            m.thread_id,
+                            sms_participants(m)
+                        );
+                    }
+                }
+            }
+            SmsCommands::Show {
+                thread,
+                limit,
+                json,
+            } => {
+                let action = SmsAction::Show {
+                    thread_id: thread,
+                    limit,
+                };
+                let messages: Vec<SmsMessage> =
+                    response_data(send(IpcRequest::Sms { device, action }).await?)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&messages)?);
+                } else {
+                    for m in &messages {
+                        let when = m.date.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                        let from = if m.incoming {
+                            sms_participants(m)
+                        } else {
+                            "me".to_string()
+                        };
+                        println!("[{when}] {from}: {}", m.body);
+                    }
+                }
+            }
+        },
+        Commands::Call { command } => match command {
+            CallCommands::End { device } => {
+                print_message(send(IpcRequest::EndCall { device }).await?);
+            }
+        },
+        Commands::Contacts { device, command } => match command {
+            ContactsCommands::Sync => {
+                print_message(
+                    send(IpcRequest::Contacts {
+                        device,
+                        action: ContactsAction::Sync,
+                    })
+                    .await?,
+                );
+            }
+            ContactsCommands::List { json } => {
+                let action = ContactsAction::List;
+                let contacts: Vec<Contact> =
+                    response_data(send(IpcRequest::Contacts { device, action }).await?)?;
+                print_contacts(&contacts, json)?;
+            }
+            ContactsCommands::Search { query, json } => {
+                let action = ContactsAction::Search { query };
+                let contacts: Vec<Contact> =
+                    response_data(send(IpcRequest::Contacts { device, action }).await?)?;
+                print_contacts(&contacts, json)?;
+            }
+            ContactsCommands::Export { output } => {
+                let action = ContactsAction::Export;
+                let vcards: String =
+                    response_data(send(IpcRequest::Contacts { device, action }).await?)?;
+                match output {
+                    Some(path) => {
+                        std::fs::write(&path, vcards)
+                            .with_context(|| format!("failed to write {path}"))?;
+                        println!("Exported contacts to {path}");
+                    }
+                    None => print!("{vcards}"),
+                }
+            }
+        },
+        Commands::Transfers { follow, json } => {
+            let mut seen: Vec<(u32, TransferState, Option<u32>)> = Vec::new();
+            loop {
+                let transfers: Vec<Transfer> =
+                    response_data(send(IpcRequest::Transfers).await?)?;
+                if json && !follow {
+                    println!("{}", serde_json::to_string_pretty(&transfers)?);
+                    break;
+                }
+                if !follow && transfers.is_empty() {
+                    println!("No transfers recorded");
+                }
+                for t in &transfers {
+                    let key = (t.id, t.state, t.percent);
+                    if seen.contains(&key) {
+                        continue;
+                    }
+                    seen.retain(|(id, _, _)| *id != t.id);
+                    seen.push(key);
+                    if json {
+                        println!("{}", serde_json::to_string(t)?);
+                    } else {
+                        println!("{}", format_transfer(t));
+                    }
+                }
+                if !follow {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+        }
+        Commands::History {
+            device,
+            kind,
+            limit,
+            json,
+            resend,
+        } => {
+            if let Some(id) = resend {
+                let action = HistoryAction::Resend { id };
+                let resp = send(IpcRequest::History { action }).await?;
+                if resp.data.is_none() {
+                    print_message(resp);
+                    return Ok(());
+                }
+                return report_share_results(&response_data::<Vec<FileShareResult>>(resp)?);
+            }
+            let action = HistoryAction::List {
+                device,
+                kind,
+                limit: Some(limit),
+            };
+            let entries: Vec<AuditEntry> =
+                response_data(send(IpcRequest::History { action }).await?)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else if entries.is_empty() {
+                println!("No matching history entries");
+            } else {
+                for e in &entries {
+                    println!("{}", format_audit_entry(e));
+                }
+            }
+        }
+        Commands::Queue { command } => match command {
+            QueueCommands::List { json } => {
+                let action = QueueAction::List;
+                let items: Vec<QueuedAction> =
+                    response_data(send(IpcRequest::Queue { action }).await?)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&items)?);
+                } else if items.is_empty() {
+                    println!("Queue is empty");
+                } else {
+                    for item in &items {
+                        println!(
+                            "#{:<4} {:<20} {}  (queued {}, expires {})",
+                            item.id,
+                            item.device,
+                            item.summary,
+                            item.queued_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                            item.expires_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        );
+                    }
+                }
+            }
+            QueueCommands::Cancel { id } => {
+                let action = QueueAction::Cancel { id };
+                print_message(send(IpcRequest::Queue { action }).await?);
+            }
+        },
+        Commands::ClipboardSync { command } => {
+            let action = match command {
+                ClipboardSyncCommands::Pause => ClipboardSyncAction::Pause,
+                ClipboardSyncCommands::Resume => ClipboardSyncAction::Resume,
+                ClipboardSyncCommands::Status => ClipboardSyncAction::Status,
             };
-            print_message(send(IpcRequest::Media { device, action }).await?);
+            print_message(send(IpcRequest::ClipboardSync { action }).await?);
         }
         Commands::Completions { shell } => {
             let mut cmd = Cli::command();
@@ -381,6 +1294,111 @@ fn print_status(state: &DaemonState) {
     }
 }
 
+/// Share resolved file paths and report the result per file.
+async fn share_files(paths: Vec<String>, device: Option<String>, queue: bool) -> Result<()> {
+    let resp = send(queued(IpcRequest::ShareFiles { paths, device }, queue)).await?;
+    if resp.ok && resp.data.is_none() {
+        // Held in the offline queue.
+        print_message(resp);
+        return Ok(());
+    }
+    report_share_results(&response_data::<Vec<FileShareResult>>(resp)?)
+}
+
+/// Print one line per shared file; fails when any file was not sent.
+fn report_share_results(results: &[FileShareResult]) -> Result<()> {
+    let failed = results.iter().filter(|r| !r.ok).count();
+    for r in results {
+        match &r.error {
+            None => println!("ok     {}", r.path),
+            Some(err) => println!("failed {}: {err}", r.path),
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{failed} of {} file(s) failed to share", results.len()));
+    }
+    Ok(())
+}
+
+fn format_audit_entry(entry: &AuditEntry) -> String {
+    let when = entry.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    let result = match (&entry.message, entry.ok) {
+        (_, true) => "ok".to_string(),
+        (Some(err), false) => format!("failed: {err}"),
+        (None, false) => "failed".to_string(),
+    };
+    format!(
+        "#{:<5} {when}  {:<16} {:<20} {}  [{result}]",
+        entry.id,
+        entry.kind,
+        entry.device.as_deref().unwrap_or("-"),
+        entry.summary,
+    )
+}
+
+fn format_media_state(state: &MediaState) -> String {
+    let mut lines = vec![format!(
+        "{} on {}: {}",
+        state.player.as_deref().unwrap_or("No player"),
+        state.device,
+        if state.playing { "playing" } else { "paused" }
+    )];
+    if let Some(title) = &state.title {
+        lines.push(format!("Title:  {title}"));
+    }
+    if let Some(artist) = &state.artist {
+        lines.push(format!("Artist: {artist}"));
+    }
+    if let Some(album) = &state.album {
+        lines.push(format!("Album:  {album}"));
+    }
+    match (state.position_ms, state.length_ms) {
+        (Some(pos), Some(len)) => lines.push(format!(
+            "Position: {} / {}",
+            format_track_time(pos),
+            format_track_time(len)
+        )),
+        (Some(pos), None) => lines.push(format!("Position: {}", format_track_time(pos))),
+        _ => {}
+    }
+    if let Some(volume) = state.volume {
+        lines.push(format!("Volume: {volume}%"));
+    }
+    if let Some(path) = &state.album_art_path {
+        lines.push(format!("Art:    {path}"));
+    }
+    lines.join("\n")
+}
+
+fn format_player_entry(entry: &MediaPlayerEntry) -> String {
+    let state = &entry.state;
+    let status = if state.playing { "playing" } else { "paused" };
+    let track = match (&state.artist, &state.title) {
+        (Some(artist), Some(title)) => format!("{artist} - {title}"),
+        (None, Some(title)) => title.clone(),
+        _ => "-".to_string(),
+    };
+    format!(
+        "{} {:<20} {:<16} {:<8} {track}",
+        if entry.active { "*" } else { " " },
+        entry.device_name,
+        state.player.as_deref().unwrap_or("-"),
+        status,
+    )
+}
+
+/// Route `request` through the daemon's offline queue when `queue` is set.
+fn queued(request: IpcRequest, queue: bool) -> IpcRequest {
+    if !queue {
+        return request;
+    }
+    IpcRequest::Queue {
+        action: QueueAction::Add {
+            request: Box::new(request),
+        },
+    }
+}
+
 fn print_message(resp: IpcResponse) {
     if resp.ok {
         println!("{}", resp.message.unwrap_or_else(|| "ok".to_string()));
@@ -390,86 +1408,523 @@ fn print_message(resp: IpcResponse) {
     }
 }
 
-fn build_waybar_payload(state: &DaemonState) -> WaybarPayload {
-    let connected = state.devices.iter().filter(|d| d.reachable).count();
-    if connected == 0 {
-        return WaybarPayload {
-            text: "󰄰".to_string(),
-            tooltip: "Phone: offline".to_string(),
-            class: "disconnected".to_string(),
+/// Key presses typing `text`, with each newline sent as Enter.
+fn text_key_presses(text: &str) -> Vec<KeyPress> {
+    let mut presses = Vec::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            presses.push(KeyPress::special(SpecialKey::Enter));
+        }
+        if !line.is_empty() {
+            presses.push(KeyPress::text(line));
+        }
+    }
+    presses
+}
+
+/// Stream keystrokes from the terminal to the phone until Ctrl-] is pressed.
+async fn stream_keys(device: Option<String>) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!("--interactive needs a terminal on stdin"));
+    }
+    eprintln!("Typing on the phone; press Ctrl-] to stop");
+    let _raw = RawTerminal::enter()?;
+    let mut stdin = tokio::io::stdin();
+    let mut buf = [0u8; 256];
+    let mut pending = Vec::new();
+    loop {
+        let n = stdin.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buf[..n]);
+        let (keys, stop) = decode_terminal_keys(&mut pending);
+        if !keys.is_empty() {
+            let resp = send(IpcRequest::SendKeys {
+                device: device.clone(),
+                keys,
+            })
+            .await?;
+            if !resp.ok {
+                // Raw mode needs explicit carriage returns.
+                let err = resp.message.unwrap_or_else(|| "action failed".to_string());
+                eprint!("{err}\r\n");
+            }
+        }
+        if stop {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Raw terminal mode set with `stty`; the previous settings are restored on drop.
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        let out = std::process::Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()
+            .context("failed to run stty")?;
+        if !out.status.success() {
+            return Err(anyhow!("stty could not read the terminal settings"));
+        }
+        let saved = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        let status = std::process::Command::new("stty")
+            .args(["raw", "-echo"])
+            .status()
+            .context("failed to run stty")?;
+        if !status.success() {
+            return Err(anyhow!("stty could not switch the terminal to raw mode"));
+        }
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("stty").arg(&self.saved).status();
+    }
+}
+
+/// Decode raw terminal input into key presses, leaving an incomplete UTF-8 tail in `pending`.
+/// Returns true once Ctrl-] is read.
+fn decode_terminal_keys(pending: &mut Vec<u8>) -> (Vec<KeyPress>, bool) {
+    fn flush(text: &mut String, keys: &mut Vec<KeyPress>) {
+        if !text.is_empty() {
+            keys.push(KeyPress::text(std::mem::take(text)));
+        }
+    }
+
+    let mut keys = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < pending.len() {
+        let byte = pending[i];
+        let (key, len) = match byte {
+            0x1d => {
+                flush(&mut text, &mut keys);
+                pending.clear();
+                return (keys, true);
+            }
+            0x1b => escape_sequence(&pending[i..]),
+            b'\r' | b'\n' => (Some(KeyPress::special(SpecialKey::Enter)), 1),
+            b'\t' => (Some(KeyPress::special(SpecialKey::Tab)), 1),
+            0x7f | 0x08 => (Some(KeyPress::special(SpecialKey::Backspace)), 1),
+            0x01..=0x1a => {
+                let letter = char::from(b'a' + byte - 1);
+                let press = KeyPress {
+                    ctrl: true,
+                    ..KeyPress::text(letter)
+                };
+                (Some(press), 1)
+            }
+            0x00..=0x1f => (None, 1),
+            _ => {
+                let rest = &pending[i..];
+                let end = rest
+                    .iter()
+                    .position(|b| *b < 0x20 || *b == 0x7f)
+                    .unwrap_or(rest.len());
+                match std::str::from_utf8(&rest[..end]) {
+                    Ok(run) => {
+                        text.push_str(run);
+                        i += end;
+                    }
+                    // A character split across reads; keep its bytes for the next one.
+                    Err(err) if err.error_len().is_none() && end == rest.len() => {
+                        let valid = err.valid_up_to();
+                        text.push_str(&String::from_utf8_lossy(&rest[..valid]));
+                        i += valid;
+                        break;
+                    }
+                    Err(_) => {
+                        text.push_str(&String::from_utf8_lossy(&rest[..end]));
+                        i += end;
+                    }
+                }
+                continue;
+            }
         };
+        flush(&mut text, &mut keys);
+        keys.extend(key);
+        i += len;
     }
+    flush(&mut text, &mut keys);
+    pending.drain(..i);
+    (keys, false)
+}
 
-    let device = state
-        .devices
-        .iter()
-        .find(|d| d.reachable)
-        .or_else(|| state.devices.first());
+/// Decode an escape sequence at the start of `seq`: CSI/SS3 keys with xterm modifiers,
+/// `ESC <char>` as Alt+char, or a lone Escape. Returns the key and the bytes consumed.
+fn escape_sequence(seq: &[u8]) -> (Option<KeyPress>, usize) {
+    match seq.get(1) {
+        Some(b'[' | b'O') => {
+            let Some(end) = seq[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+                return (None, seq.len());
+            };
+            let params = std::str::from_utf8(&seq[2..2 + end]).unwrap_or_default();
+            let mut fields = params.split(';');
+            let number = fields.next().unwrap_or_default();
+            // xterm encodes modifiers as 1 + (shift | alt << 1 | ctrl << 2).
+            let modifiers = fields
+                .next()
+                .and_then(|m| m.parse::<u8>().ok())
+                .map_or(0, |m| m.saturating_sub(1));
+            let special = match (seq[2 + end], number) {
+                (b'A', _) => Some(SpecialKey::Up),
+                (b'B', _) => Some(SpecialKey::Down),
+                (b'C', _) => Some(SpecialKey::Right),
+                (b'D', _) => Some(SpecialKey::Left),
+                (b'H', _) | (b'~', "1" | "7") => Some(SpecialKey::Home),
+                (b'F', _) | (b'~', "4" | "8") => Some(SpecialKey::End),
+                (b'P', _) | (b'~', "11") => Some(SpecialKey::F1),
+                (b'Q', _) | (b'~', "12") => Some(SpecialKey::F2),
+                (b'R', _) | (b'~', "13") => Some(SpecialKey::F3),
+                (b'S', _) | (b'~', "14") => Some(SpecialKey::F4),
+                (b'~', "3") => Some(SpecialKey::Delete),
+                (b'~', "5") => Some(SpecialKey::PageUp),
+                (b'~', "6") => Some(SpecialKey::PageDown),
+                (b'~', "15") => Some(SpecialKey::F5),
+                (b'~', "17") => Some(SpecialKey::F6),
+                (b'~', "18") => Some(SpecialKey::F7),
+                (b'~', "19") => Some(SpecialKey::F8),
+                (b'~', "20") => Some(SpecialKey::F9),
+                (b'~', "21") => Some(SpecialKey::F10),
+                (b'~', "23") => Some(SpecialKey::F11),
+                (b'~', "24") => Some(SpecialKey::F12),
+                _ => None,
+            };
+            let key = special.map(|special| KeyPress {
+                shift: modifiers & 1 != 0,
+                alt: modifiers & 2 != 0,
+                ctrl: modifiers & 4 != 0,
+                ..KeyPress::special(special)
+            });
+            (key, end + 3)
+        }
+        Some(&byte) if (0x20..0x7f).contains(&byte) => {
+            let press = KeyPress {
+                alt: true,
+                ..KeyPress::text(char::from(byte))
+            };
+            (Some(press), 2)
+        }
+        _ => (Some(KeyPress::special(SpecialKey::Escape)), 1),
+    }
+}
 
-    if let Some(d) = device {
-        let battery_percent = d.battery_percent;
-        let battery = battery_percent
-            .map(|v| format!("{v}%"))
-            .unwrap_or_else(|| "--".to_string());
-        let signal_icon = cellular_signal_icon(d.signal_percent);
-        let mount_suffix = if d.mounted { " 󰛳" } else { "" };
-        let charge_suffix = if d.charging == Some(true) { " " } else { "" };
-        let text = format!("{signal_icon} 󰄜{mount_suffix} {battery}{charge_suffix}");
+fn read_stdin() -> Result<String> {
+    if io::stdin().is_terminal() {
+        eprintln!("Reading message from stdin; finish with Ctrl-D");
+    }
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .context("failed to read stdin")?;
+    Ok(text.trim_end_matches('\n').to_string())
+}
+
+/// Parse `--since` values: relative ages (`30m`, `2h`, `7d`), dates, or RFC 3339 timestamps.
+fn parse_since(raw: &str) -> Result<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|dt| dt.and_local_timezone(Local).earliest())
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("invalid date '{raw}'"));
+    }
 
-        let class = match battery_percent {
-            Some(b) if b < 30 => "crit",
-            Some(b) if b < 50 => "warn",
-            _ => "ok",
+    // Split before the last character, not byte, so multibyte input is rejected cleanly.
+    let split = raw.char_indices().last().map_or(0, |(i, _)| i);
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| anyhow!("invalid --since value '{raw}'"))?;
+    let age = match unit {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => return Err(anyhow!("invalid --since value '{raw}'")),
+    };
+    age.and_then(|age| Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| anyhow!("--since value '{raw}' is out of range"))
+}
+
+/// Resolve CLI path arguments to readable absolute file paths, expanding globs and directories.
+async fn resolve_share_paths(args: &[String], dirs: DirMode) -> Result<Vec<String>> {
+    let mut out: Vec<String> = Vec::new();
+    for arg in args {
+        for path in expand_glob(arg)? {
+            let path = std::fs::canonicalize(&path)
+                .with_context(|| format!("cannot resolve path: {}", path.display()))?;
+            let files = if path.is_dir() {
+                match dirs {
+                    DirMode::Expand => {
+                        let mut files = Vec::new();
+                        collect_files(&path, &mut files)?;
+                        if files.is_empty() {
+                            return Err(anyhow!("directory is empty: {}", path.display()));
+                        }
+                        files
+                    }
+                    DirMode::Zip => vec![zip_directory(&path).await?],
+                }
+            } else {
+                vec![path]
+            };
+            for file in files {
+                std::fs::File::open(&file)
+                    .with_context(|| format!("cannot read file: {}", file.display()))?;
+                let file = file.to_string_lossy().to_string();
+                if !out.contains(&file) {
+                    out.push(file);
+                }
+            }
         }
-        .to_string();
+    }
+    Ok(out)
+}
 
-        let signal_text = d
-            .signal_percent
-            .map(|v| format!("{v}%"))
-            .unwrap_or_else(|| "--".to_string());
-        let network_type = d.network_type.as_deref().unwrap_or("Unknown");
-        let mount_status = if d.mounted { "Yes" } else { "No" };
-        let mount_point = if d.mounted {
-            d.mount_point.as_deref().unwrap_or("--")
+/// Expand `*` and `?` in the last path component; other arguments are returned as-is.
+fn expand_glob(arg: &str) -> Result<Vec<std::path::PathBuf>> {
+    let path = std::path::Path::new(arg);
+    let pattern = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if path.exists() || !pattern.contains(['*', '?']) {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    let mut matches: Vec<std::path::PathBuf> = std::fs::read_dir(&dir)
+        .with_context(|| format!("cannot read directory: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            // Like the shell, wildcards do not match hidden files.
+            (!name.starts_with('.') || pattern.starts_with('.')) && wildcard_match(pattern, &name)
+        })
+        .map(|e| e.path())
+        .collect();
+    if matches.is_empty() {
+        return Err(anyhow!("no files match {arg}"));
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ni));
+            pi += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ni = matched + 1;
+            backtrack = Some((star, matched + 1));
         } else {
-            "--"
-        };
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
 
-        let tooltip = format!(
-            "{}\nBattery: {}\nStatus: {}\nPaired: {}\nMounted: {}\nMount point: {}\nSignal: {}\nNetwork: {}\nDevices connected: {}",
-            d.name,
-            battery,
-            if d.reachable { "Connected" } else { "Offline" },
-            if d.paired { "Yes" } else { "No" },
-            mount_status,
-            mount_point,
-            signal_text,
-            network_type,
-            connected,
-        );
-
-        return WaybarPayload {
-            text,
-            tooltip,
-            class,
-        };
+fn collect_files(dir: &std::path::Path, out: &mut Vec<std::path::PathBuf>) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("cannot read directory: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        // Skip symlinked directories to avoid cycles.
+        if path.is_dir() && !path.is_symlink() {
+            collect_files(&path, out)?;
+        } else if path.is_file() {
+            out.push(path);
+        }
     }
+    Ok(())
+}
 
-    WaybarPayload {
-        text: "󰄰".to_string(),
-        tooltip: "Phone: unavailable".to_string(),
-        class: "disconnected".to_string(),
+/// Archive `dir` with `zip` into `${XDG_RUNTIME_DIR}/hyprconnect/outgoing/`.
+async fn zip_directory(dir: &std::path::Path) -> Result<std::path::PathBuf> {
+    if !command_exists("zip").await {
+        return Err(anyhow!("`zip` is not installed; install it or use --dirs expand"));
     }
+    let out_dir = state_export_dir().join("outgoing");
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("failed to create {}", out_dir.display()))?;
+    prune_outgoing(&out_dir);
+
+    let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("folder");
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let archive = out_dir.join(format!("{name}-{stamp}.zip"));
+    let parent = dir.parent().unwrap_or(dir);
+    let status = Command::new("zip")
+        .current_dir(parent)
+        .arg("-qr")
+        .arg(&archive)
+        .arg(name)
+        .stdout(Stdio::null())
+        .status()
+        .await
+        .context("failed to execute zip")?;
+    if !status.success() {
+        return Err(anyhow!("zip failed for {}", dir.display()));
+    }
+    Ok(archive)
+}
+
+/// Archives are removed a day after creation; kdeconnectd reads them asynchronously.
+fn prune_outgoing(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|m| m.elapsed().ok());
+        if age.is_some_and(|a| a.as_secs() > 24 * 3600) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Refuse `--device`/`--target` for `list-all` and `pause-all`, which cover every device.
+fn reject_media_target(
+    command: &str,
+    device: Option<&str>,
+    target: Option<MediaTarget>,
+) -> Result<()> {
+    if device.is_some() || target.is_some() {
+        return Err(anyhow!(
+            "media {command} acts on every reachable device; drop --device/--target"
+        ));
+    }
+    Ok(())
+}
+
+/// Read the clipboard in the caller's Wayland session. `None` leaves the read to the daemon.
+async fn capture_clipboard(primary: bool) -> Option<ClipboardCapture> {
+    let selection: &[&str] = if primary { &["--primary"] } else { &[] };
+    let types = Command::new("wl-paste")
+        .args(selection)
+        .arg("--list-types")
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let types = String::from_utf8_lossy(&types.stdout);
+    let offered: Vec<&str> = types.lines().map(str::trim).collect();
+    let mime = pick_clipboard_type(&offered)?;
+
+    let data = Command::new("wl-paste")
+        .args(selection)
+        .args(["--no-newline", "--type", &mime])
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?
+        .stdout;
+    Some(ClipboardCapture { mime, data })
+}
+
+fn format_transfer(t: &Transfer) -> String {
+    let state = match t.state {
+        TransferState::Running => "running",
+        TransferState::Completed => "done",
+        TransferState::Failed => "failed",
+        TransferState::Cancelled => "cancelled",
+    };
+    let progress = match t.total_bytes {
+        Some(total) => format!("{}/{}", format_bytes(t.processed_bytes), format_bytes(total)),
+        None => format_bytes(t.processed_bytes),
+    };
+    let percent = t.percent.map(|p| format!(" {p}%")).unwrap_or_default();
+    let speed = if t.state == TransferState::Running && t.speed > 0 {
+        format!(" {}/s", format_bytes(t.speed))
+    } else {
+        String::new()
+    };
+    let name = t.file.as_deref().unwrap_or(&t.title);
+    let error = t.error.as_deref().map(|e| format!(" ({e})")).unwrap_or_default();
+    format!("#{} {state}{percent} {progress}{speed} {name}{error}", t.id)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Addresses of an SMS thread, using contact names where the daemon resolved them.
+fn sms_participants(message: &SmsMessage) -> String {
+    message
+        .addresses
+        .iter()
+        .enumerate()
+        .map(|(i, address)| match message.contacts.get(i).filter(|n| !n.is_empty()) {
+            Some(name) => name.clone(),
+            None => address.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_contacts(contacts: &[Contact], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(contacts)?);
+    } else if contacts.is_empty() {
+        println!("No contacts found");
+    } else {
+        for c in contacts {
+            let mut details = c.phones.clone();
+            details.extend(c.emails.iter().cloned());
+            println!("{}: {}", c.name, details.join(", "));
+        }
+    }
+    Ok(())
 }
 
-fn cellular_signal_icon(signal_percent: Option<u8>) -> &'static str {
-    match signal_percent {
-        Some(v) if v >= 75 => "󰣺",
-        Some(v) if v >= 50 => "󰣸",
-        Some(v) if v >= 30 => "󰣶",
-        Some(v) if v >= 10 => "󰣴",
-        _ => "󰣾",
+fn response_data<T: serde::de::DeserializeOwned>(resp: IpcResponse) -> Result<T> {
+    if !resp.ok {
+        return Err(anyhow!(resp.message.unwrap_or_else(|| "action failed".to_string())));
     }
+    let data = resp.data.ok_or_else(|| anyhow!("daemon returned no data"))?;
+    serde_json::from_value(data).context("invalid daemon response data")
 }
 
 async fn send(req: IpcRequest) -> Result<IpcResponse> {
@@ -522,6 +1977,12 @@ async fn run_doctor() {
                     report("plugin mprisremote", mprisremote);
                     report("plugin mpriscontrol", mpriscontrol);
                     report("plugin systemvolume", systemvolume);
+                    let sms = plugin_supported(&device.id, "kdeconnect_sms").await;
+                    report("plugin sms", sms);
+                    let contacts = plugin_supported(&device.id, "kdeconnect_contacts").await;
+                    report("plugin contacts", contacts);
+                    let clipboard = plugin_supported(&device.id, "kdeconnect_clipboard").await;
+                    report("plugin clipboard", clipboard);
                 }
             }
         }
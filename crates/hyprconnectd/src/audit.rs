@@ -0,0 +1,358 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use hyprconnect_core::{
+    state_dir, AuditEntry, ClipboardSyncAction, ContactsAction, FileShareResult, HistoryAction,
+    IpcRequest, IpcResponse, MediaAction, NotificationAction, SmsAction,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{handle_request, into_data_response, into_response, resolve_device, Shared};
+
+/// Rotated generations kept next to the live log (`audit.jsonl.1` .. `audit.jsonl.3`).
+const ROTATIONS: usize = 3;
+
+/// Append-only JSONL log of handled actions in `$XDG_STATE_HOME/hyprconnect/audit.jsonl`.
+#[derive(Default)]
+pub struct AuditLog {
+    next_id: Option<u64>,
+}
+
+impl AuditLog {
+    async fn append(&mut self, mut entry: AuditEntry, max_bytes: u64) -> Result<()> {
+        let path = log_path()?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("failed to create state dir: {}", dir.display()))?;
+        }
+        let id = match self.next_id {
+            Some(id) => id,
+            None => read_entries().await?.last().map_or(1, |e| e.id + 1),
+        };
+        entry.id = id;
+        self.next_id = Some(id + 1);
+
+        let size = tokio::fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+        if size >= max_bytes {
+            rotate(&path).await;
+        }
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to open audit log: {}", path.display()))?;
+        file.write_all(&line).await?;
+        Ok(())
+    }
+}
+
+pub async fn handle_action(shared: &Shared, action: HistoryAction) -> IpcResponse {
+    match action {
+        HistoryAction::List {
+            device,
+            kind,
+            limit,
+        } => {
+            let result = read_entries().await.map(|entries| {
+                let mut entries: Vec<AuditEntry> = entries
+                    .into_iter()
+                    .filter(|e| device.as_ref().is_none_or(|d| e.device.as_ref() == Some(d)))
+                    .filter(|e| kind.as_deref().is_none_or(|k| kind_matches(&e.kind, k)))
+                    .collect();
+                if let Some(limit) = limit {
+                    let excess = entries.len().saturating_sub(limit);
+                    entries.drain(..excess);
+                }
+                entries
+            });
+            into_data_response(result)
+        }
+        HistoryAction::Resend { id } => {
+            let entry = match read_entries().await {
+                Ok(entries) => entries.into_iter().find(|e| e.id == id),
+                Err(err) => return into_response(Err(err)),
+            };
+            match entry.and_then(|e| e.request) {
+                Some(request) => replay(shared, request)
+                    .await
+                    .unwrap_or_else(|err| into_response(Err(err))),
+                None => into_response(Err(anyhow!(
+                    "history entry {id} does not exist or cannot be re-sent"
+                ))),
+            }
+        }
+    }
+}
+
+/// Whether `request` changes something and belongs in the log; lookups and the queue and
+/// history requests themselves are left out (queued actions are logged when delivered), as is
+/// remote keyboard input, which may be a password and arrives a keystroke at a time.
+pub fn is_audited(request: &IpcRequest) -> bool {
+    match request {
+        IpcRequest::GetState
+        | IpcRequest::Transfers
+        | IpcRequest::Queue { .. }
+        | IpcRequest::History { .. }
+        | IpcRequest::SendKeys { .. } => false,
+        IpcRequest::Media { action, .. } => {
+            !matches!(
+                action,
+                MediaAction::Status
+                    | MediaAction::PlayerList
+                    | MediaAction::Watch { .. }
+                    | MediaAction::ListAll
+            )
+        }
+        IpcRequest::Notifications { action, .. } => matches!(
+            action,
+            NotificationAction::Dismiss { .. } | NotificationAction::Reply { .. }
+        ),
+        IpcRequest::Sms { action, .. } => match action {
+            SmsAction::List | SmsAction::Show { .. } => false,
+        },
+        IpcRequest::Contacts { action, .. } => matches!(action, ContactsAction::Sync),
+        IpcRequest::ClipboardSync { action } => !matches!(action, ClipboardSyncAction::Status),
+        _ => true,
+    }
+}
+
+/// Log the outcome of an audited request.
+pub async fn record(shared: &Shared, request: IpcRequest, result: &Result<IpcResponse>) {
+    if !shared.config.audit_log_enabled {
+        return;
+    }
+    let device = match target_device(&request) {
+        Some(Some(device)) => Some(device),
+        // The handler picked the device; resolving again gives the same answer.
+        Some(None) => resolve_device(shared, None).await.ok(),
+        None => None,
+    };
+    let (ok, message) = match result {
+        Ok(resp) => match outcome(resp) {
+            Ok(()) => (true, resp.message.clone()),
+            Err(err) => (false, Some(err)),
+        },
+        Err(err) => (false, Some(err.to_string())),
+    };
+    let resendable = matches!(
+        request,
+        IpcRequest::ShareFile { .. }
+            | IpcRequest::ShareFiles { .. }
+            | IpcRequest::ShareUrl { .. }
+            | IpcRequest::ShareText { .. }
+            | IpcRequest::Ping { .. }
+    );
+    let entry = AuditEntry {
+        id: 0,
+        timestamp: Utc::now(),
+        kind: request_kind(&request),
+        device: device.clone(),
+        summary: summarize(&request),
+        ok,
+        message,
+        request: resendable.then(|| match &device {
+            Some(device) => with_device(request, device),
+            None => request,
+        }),
+    };
+    let max_bytes = shared.config.audit_log_max_bytes;
+    if let Err(err) = shared.audit.lock().await.append(entry, max_bytes).await {
+        eprintln!("audit log write failed: {err:#}");
+    }
+}
+
+/// A request succeeded when the daemon accepted it and, for multi-file shares, every file
+/// went out.
+pub fn outcome(resp: &IpcResponse) -> std::result::Result<(), String> {
+    if !resp.ok {
+        return Err(resp
+            .message
+            .clone()
+            .unwrap_or_else(|| "action failed".to_string()));
+    }
+    let results = resp
+        .data
+        .clone()
+        .and_then(|data| serde_json::from_value::<Vec<FileShareResult>>(data).ok());
+    if let Some(results) = results {
+        let failed = results.iter().filter(|r| !r.ok).count();
+        if failed > 0 {
+            return Err(format!("{failed} of {} file(s) failed", results.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Run a request through the handler again (re-sends, queued deliveries). The handler reaches
+/// the callers through `IpcRequest::History`/`Queue`; the explicit `Send` box breaks that cycle
+/// for the compiler.
+pub fn replay(
+    shared: &Shared,
+    request: IpcRequest,
+) -> Pin<Box<dyn Future<Output = Result<IpcResponse>> + Send + '_>> {
+    Box::pin(handle_request(shared, request))
+}
+
+/// Short human description of a request, e.g. `file app.apk` or `media next`.
+pub fn summarize(request: &IpcRequest) -> String {
+    match request {
+        IpcRequest::ShareFile { path, .. } => format!("file {}", file_name(path)),
+        IpcRequest::ShareFiles { paths, .. } => match paths.as_slice() {
+            [one] => format!("file {}", file_name(one)),
+            _ => {
+                let names: Vec<String> = paths.iter().take(3).map(|p| file_name(p)).collect();
+                let more = if paths.len() > 3 { ", ..." } else { "" };
+                format!("{} files: {}{more}", paths.len(), names.join(", "))
+            }
+        },
+        IpcRequest::ShareUrl { url, .. } => format!("URL {url}"),
+        IpcRequest::ShareText { text, .. } => {
+            let preview: String = text.chars().take(40).collect();
+            let more = if text.chars().count() > 40 { "..." } else { "" };
+            format!("text \"{preview}{more}\"")
+        }
+        IpcRequest::ShareClipboard { capture, .. } => match capture {
+            Some(capture) => format!("clipboard ({})", capture.mime),
+            None => "clipboard".to_string(),
+        },
+        IpcRequest::Ping { message, .. } => match message {
+            Some(message) => format!("ping \"{message}\""),
+            None => "ping".to_string(),
+        },
+        IpcRequest::SendSms {
+            recipients,
+            attachments,
+            ..
+        } => {
+            let mut summary = format!("sms to {}", recipients.join(", "));
+            if !attachments.is_empty() {
+                summary.push_str(&format!(" with {} attachment(s)", attachments.len()));
+            }
+            summary
+        }
+        IpcRequest::Media { action, .. } => format!("media {}", action_name(action)),
+        IpcRequest::Notifications { action, .. } => {
+            format!("notifications {}", action_name(action))
+        }
+        IpcRequest::Sms { action, .. } => format!("sms {}", action_name(action)),
+        IpcRequest::Contacts { action, .. } => format!("contacts {}", action_name(action)),
+        IpcRequest::ClipboardSync { action } => format!("clipboard sync {}", action_name(action)),
+        other => request_kind(other).replace('_', " "),
+    }
+}
+
+/// `Some(device)` for requests aimed at a device (`Some(None)` when the daemon chooses it),
+/// `None` for device-independent requests.
+fn target_device(request: &IpcRequest) -> Option<Option<String>> {
+    match request {
+        IpcRequest::Pair { device } | IpcRequest::Unpair { device } => Some(Some(device.clone())),
+        // The device follows playback, so resolving it here could name the wrong one.
+        IpcRequest::Media {
+            auto_target: true, ..
+        } => None,
+        IpcRequest::ShareFile { device, .. }
+        | IpcRequest::ShareUrl { device, .. }
+        | IpcRequest::ShareFiles { device, .. }
+        | IpcRequest::ShareText { device, .. }
+        | IpcRequest::ShareClipboard { device, .. }
+        | IpcRequest::Ping { device, .. }
+        | IpcRequest::Find { device }
+        | IpcRequest::Mount { device }
+        | IpcRequest::OpenMount { device }
+        | IpcRequest::ToggleMount { device }
+        | IpcRequest::Media { device, .. }
+        | IpcRequest::Notifications { device, .. }
+        | IpcRequest::SendSms { device, .. }
+        | IpcRequest::Sms { device, .. }
+        | IpcRequest::Contacts { device, .. } => Some(device.clone()),
+        // Ending a call without a device acts on every device in a call.
+        IpcRequest::EndCall { device } => device.clone().map(Some),
+        _ => None,
+    }
+}
+
+/// Point a share or ping request at `id`.
+pub fn with_device(mut request: IpcRequest, id: &str) -> IpcRequest {
+    match &mut request {
+        IpcRequest::ShareFile { device, .. }
+        | IpcRequest::ShareFiles { device, .. }
+        | IpcRequest::ShareUrl { device, .. }
+        | IpcRequest::ShareText { device, .. }
+        | IpcRequest::Ping { device, .. } => *device = Some(id.to_string()),
+        _ => {}
+    }
+    request
+}
+
+/// `share_files` matches `share_files` and `share`, but not `share_file`.
+fn kind_matches(kind: &str, wanted: &str) -> bool {
+    kind == wanted
+        || kind
+            .strip_prefix(wanted)
+            .is_some_and(|rest| rest.starts_with('_'))
+}
+
+fn request_kind(request: &IpcRequest) -> String {
+    request.kind().to_string()
+}
+
+fn action_name<T: serde::Serialize>(action: &T) -> String {
+    serde_tag(action, "action").replace('_', " ")
+}
+
+fn serde_tag<T: serde::Serialize>(value: &T, tag: &str) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.get(tag).and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Entries from the rotated files and the live log, oldest first.
+async fn read_entries() -> Result<Vec<AuditEntry>> {
+    let path = log_path()?;
+    let mut entries = Vec::new();
+    for n in (0..=ROTATIONS).rev() {
+        let Ok(raw) = tokio::fs::read_to_string(rotated_path(&path, n)).await else {
+            continue;
+        };
+        entries.extend(
+            raw.lines()
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok()),
+        );
+    }
+    Ok(entries)
+}
+
+async fn rotate(path: &Path) {
+    for n in (1..ROTATIONS).rev() {
+        let _ = tokio::fs::rename(rotated_path(path, n), rotated_path(path, n + 1)).await;
+    }
+    let _ = tokio::fs::rename(path, rotated_path(path, 1)).await;
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+fn log_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("audit.jsonl"))
+}
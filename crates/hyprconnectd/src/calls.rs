@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use hyprconnect_core::NotificationUrgency;
+use notify_rust::Notification;
+use zbus::proxy::CacheProperties;
+
+use crate::notifications::{close_desktop_notification, desktop_urgency, ActionTarget};
+use crate::{export_state, Shared};
+
+pub const TELEPHONY_IFACE: &str = "org.kde.kdeconnect.device.telephony";
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_IFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+struct ActiveCall {
+    /// Desktop "Incoming call" notification, closed once the call is answered or ends.
+    desktop_id: Option<u32>,
+    /// Bumped on every ring/answer so a stale timeout never ends a newer call.
+    serial: u64,
+}
+
+/// Calls in progress per device, plus the local MPRIS players hyprconnect paused for them.
+#[derive(Default)]
+pub struct CallTracker {
+    calls: HashMap<String, ActiveCall>,
+    paused_players: Vec<String>,
+    next_serial: u64,
+}
+
+impl CallTracker {
+    pub fn in_call(&self, device: &str) -> bool {
+        self.calls.contains_key(device)
+    }
+}
+
+/// Handle `callReceived(event, number, contactName)` from the telephony plugin.
+pub async fn handle_signal(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    device: &str,
+    event: &str,
+    number: &str,
+    contact: &str,
+) -> Result<()> {
+    let contact = if contact.is_empty() || contact == number {
+        let mut book = shared.contacts.lock().await;
+        book.lookup(device, number).await.unwrap_or_default()
+    } else {
+        contact.to_string()
+    };
+    let caller = if contact.is_empty() {
+        number.to_string()
+    } else {
+        format!("{contact} ({number})")
+    };
+
+    match event {
+        "ringing" => {
+            let desktop_id = notify_call(shared, device, "Incoming call", &caller, true).await;
+            start_call(shared, conn, device, desktop_id).await;
+        }
+        "talking" => {
+            start_call(shared, conn, device, None).await;
+        }
+        "missedCall" => {
+            end_call(shared, conn, device).await;
+            notify_call(shared, device, "Missed call", &caller, false).await;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn start_call(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    device: &str,
+    desktop_id: Option<u32>,
+) {
+    let (first_call, stale_desktop_id, serial) = {
+        let mut tracker = shared.calls.lock().await;
+        let first_call = tracker.calls.is_empty();
+        tracker.next_serial += 1;
+        let serial = tracker.next_serial;
+        let previous = tracker
+            .calls
+            .insert(device.to_string(), ActiveCall { desktop_id, serial });
+        // Answering closes the ringing notification; a new ring replaces the old one.
+        let stale = previous
+            .and_then(|p| p.desktop_id)
+            .filter(|id| Some(*id) != desktop_id);
+        (first_call, stale, serial)
+    };
+
+    if let Some(id) = stale_desktop_id {
+        let _ = close_desktop_notification(conn, id).await;
+    }
+
+    if first_call && shared.config.pause_media_during_calls {
+        match pause_local_players(conn).await {
+            Ok(paused) => shared.calls.lock().await.paused_players = paused,
+            Err(err) => eprintln!("failed to pause local media: {err:#}"),
+        }
+    }
+
+    set_in_call(shared, device, true).await;
+    schedule_timeout(shared, conn, device, serial);
+}
+
+/// KDE Connect handles the phone's hang-up packet internally and emits no D-Bus signal
+/// for it, so a call with no further telephony event is treated as over after
+/// `call_timeout_minutes`.
+fn schedule_timeout(shared: &Shared, conn: &zbus::Connection, device: &str, serial: u64) {
+    let minutes = shared.config.call_timeout_minutes;
+    if minutes == 0 {
+        return;
+    }
+    let shared = shared.clone();
+    let conn = conn.clone();
+    let device = device.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+        let current = shared
+            .calls
+            .lock()
+            .await
+            .calls
+            .get(&device)
+            .is_some_and(|call| call.serial == serial);
+        if current {
+            end_call(&shared, &conn, &device).await;
+        }
+    });
+}
+
+/// Clear the call for `device`; resumes paused players once no call is left.
+pub async fn end_call(shared: &Shared, conn: &zbus::Connection, device: &str) -> bool {
+    let (ended, resume) = {
+        let mut tracker = shared.calls.lock().await;
+        let ended = tracker.calls.remove(device);
+        let resume = if tracker.calls.is_empty() {
+            std::mem::take(&mut tracker.paused_players)
+        } else {
+            Vec::new()
+        };
+        (ended, resume)
+    };
+
+    if let Some(id) = ended.as_ref().and_then(|c| c.desktop_id) {
+        let _ = close_desktop_notification(conn, id).await;
+    }
+    if !resume.is_empty() {
+        if let Err(err) = resume_local_players(conn, &resume).await {
+            eprintln!("failed to resume local media: {err:#}");
+        }
+    }
+    if ended.is_some() {
+        set_in_call(shared, device, false).await;
+    }
+    ended.is_some()
+}
+
+pub async fn mute_ringer(conn: &zbus::Connection, device: &str) -> Result<()> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{device}/telephony"))?
+        .interface(TELEPHONY_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    proxy
+        .call_method("sendMutePacket", &())
+        .await
+        .context("telephony plugin rejected the mute request")?;
+    Ok(())
+}
+
+async fn set_in_call(shared: &Shared, device: &str, in_call: bool) {
+    let snapshot = {
+        let mut state = shared.state.write().await;
+        let Some(d) = state.devices.iter_mut().find(|d| d.id == device) else {
+            return;
+        };
+        d.in_call = in_call;
+        state.clone()
+    };
+    export_state(shared, &snapshot).await;
+}
+
+async fn notify_call(
+    shared: &Shared,
+    device: &str,
+    summary: &str,
+    caller: &str,
+    ringing: bool,
+) -> Option<u32> {
+    if !shared.config.notifications_enabled || !shared.config.call_notifications_enabled {
+        return None;
+    }
+    let urgency = if ringing {
+        NotificationUrgency::Critical
+    } else {
+        NotificationUrgency::Normal
+    };
+    let decision = shared
+        .config
+        .notification_rules
+        .evaluate("Phone", summary, caller, urgency, Local::now());
+    if !decision.show {
+        return None;
+    }
+
+    let mut notification = Notification::new();
+    notification
+        .appname("Phone")
+        .summary(summary)
+        .body(caller)
+        .icon(if ringing { "call-start" } else { "call-missed" })
+        .urgency(desktop_urgency(decision.urgency));
+    if ringing {
+        notification.action("mute", "Mute ringer");
+    }
+    let handle = notification.show().ok()?;
+    let id = handle.id();
+    if ringing {
+        let target = ActionTarget::Call {
+            device: device.to_string(),
+        };
+        shared.mirror.lock().await.register_actions(id, target);
+    }
+    Some(id)
+}
+
+/// Pause every playing MPRIS player on the session bus; returns the bus names paused.
+async fn pause_local_players(conn: &zbus::Connection) -> Result<Vec<String>> {
+    let dbus = zbus::fdo::DBusProxy::new(conn).await?;
+    let names = dbus.list_names().await?;
+
+    let mut paused = Vec::new();
+    for name in names {
+        let name = name.to_string();
+        // kdeconnect's MPRIS bridges expose the phone's own players; leave those alone.
+        if !name.starts_with(MPRIS_PREFIX) || name.contains("kdeconnect") {
+            continue;
+        }
+        let Ok(proxy) = mpris_proxy(conn, &name).await else {
+            continue;
+        };
+        let status: String = proxy.get_property("PlaybackStatus").await.unwrap_or_default();
+        if status != "Playing" {
+            continue;
+        }
+        if proxy.call_method("Pause", &()).await.is_ok() {
+            paused.push(name);
+        }
+    }
+    Ok(paused)
+}
+
+async fn resume_local_players(conn: &zbus::Connection, names: &[String]) -> Result<()> {
+    let mut failed = Vec::new();
+    for name in names {
+        let Ok(proxy) = mpris_proxy(conn, name).await else {
+            continue;
+        };
+        // Respect players the user stopped or restarted manually during the call.
+        let status: String = proxy.get_property("PlaybackStatus").await.unwrap_or_default();
+        if status != "Paused" {
+            continue;
+        }
+        if proxy.call_method("Play", &()).await.is_err() {
+            failed.push(name.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow!("could not resume: {}", failed.join(", ")));
+    }
+    Ok(())
+}
+
+async fn mpris_proxy(conn: &zbus::Connection, name: &str) -> Result<zbus::Proxy<'static>> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+        .destination(name.to_string())?
+        .path(MPRIS_PATH)?
+        .interface(MPRIS_PLAYER_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
@@ -0,0 +1,198 @@
+use std::os::unix::fs::FileTypeExt;
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context, Result};
+use hyprconnect_core::{runtime_dir, ClipboardSyncAction};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use zbus::proxy::CacheProperties;
+
+use crate::{require_plugin, Shared};
+
+const CLIPBOARD_IFACE: &str = "org.kde.kdeconnect.device.clipboard";
+/// Set by password managers (KeePassXC, Bitwarden, ...) on secrets that must not be synced.
+const PASSWORD_HINT_MIME: &str = "x-kde-passwordManagerHint";
+
+/// Runtime state of the desktop-to-phone clipboard sync.
+#[derive(Default)]
+pub struct ClipboardSync {
+    paused: bool,
+    /// Last content seen on either side; a change back to it is an echo and is not re-sent.
+    last: Option<String>,
+}
+
+pub async fn handle_action(shared: &Shared, action: ClipboardSyncAction) -> Result<String> {
+    if !shared.config.clipboard_sync_enabled {
+        return Err(anyhow!(
+            "clipboard sync is disabled; set clipboard_sync_enabled = true in config.toml"
+        ));
+    }
+    let mut sync = shared.clipboard.lock().await;
+    match action {
+        ClipboardSyncAction::Pause => {
+            sync.paused = true;
+            Ok("Clipboard sync paused".to_string())
+        }
+        ClipboardSyncAction::Resume => {
+            sync.paused = false;
+            Ok("Clipboard sync resumed".to_string())
+        }
+        ClipboardSyncAction::Status => Ok(if sync.paused {
+            "Clipboard sync: paused".to_string()
+        } else {
+            "Clipboard sync: running".to_string()
+        }),
+    }
+}
+
+/// Watch the Wayland clipboard with `wl-paste --watch` and push text changes to the phones.
+///
+/// Phone-side changes are applied to the desktop clipboard by kdeconnectd's clipboard plugin;
+/// KDE Connect exposes no D-Bus signal carrying the content, so hyprconnect does not write the
+/// desktop clipboard itself. Those changes show up here like any other and are forwarded to the
+/// other devices; the originating phone ignores identical content, and its confirmation
+/// round-trip matches `last` so it stops there.
+pub async fn watch(shared: Shared) -> Result<()> {
+    let env = wayland_env()?;
+    let mut child = Command::new("wl-paste")
+        .args(["--watch", "echo"])
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to execute wl-paste --watch")?;
+    let stdout = child.stdout.take().context("wl-paste --watch has no stdout")?;
+    let mut changes = BufReader::new(stdout).lines();
+
+    // wl-paste reports the current selection once on startup; take it as the baseline, even
+    // when it holds no text.
+    let mut baseline = true;
+    while changes.next_line().await?.is_some() {
+        let first = std::mem::take(&mut baseline);
+        let Some(content) = read_text_selection(&env, shared.config.clipboard_max_bytes).await
+        else {
+            continue;
+        };
+        {
+            let mut sync = shared.clipboard.lock().await;
+            if sync.last.as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            sync.last = Some(content.clone());
+            if sync.paused || first {
+                continue;
+            }
+        }
+        push_to_devices(&shared, &content).await;
+    }
+    Err(anyhow!("wl-paste --watch exited"))
+}
+
+/// Environment for `wl-paste` when the daemon was started without `WAYLAND_DISPLAY`, e.g. as a
+/// systemd user service: the first `wayland-*` socket in `XDG_RUNTIME_DIR`.
+fn wayland_env() -> Result<Vec<(String, String)>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return Ok(Vec::new());
+    }
+    let mut sockets: Vec<String> = std::fs::read_dir(runtime_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_socket()))
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("wayland-"))
+                .collect()
+        })
+        .unwrap_or_default();
+    sockets.sort();
+    let display = sockets.into_iter().next().ok_or_else(|| {
+        anyhow!("WAYLAND_DISPLAY is not set and no Wayland socket was found in XDG_RUNTIME_DIR")
+    })?;
+    Ok(vec![("WAYLAND_DISPLAY".to_string(), display)])
+}
+
+async fn push_to_devices(shared: &Shared, content: &str) {
+    let devices: Vec<String> = {
+        let state = shared.state.read().await;
+        state
+            .devices
+            .iter()
+            .filter(|d| d.reachable && d.paired)
+            .filter(|d| {
+                shared.config.clipboard_sync_devices.is_empty()
+                    || shared.config.clipboard_sync_devices.contains(&d.id)
+            })
+            .map(|d| d.id.clone())
+            .collect()
+    };
+    if devices.is_empty() {
+        return;
+    }
+    let conn = match zbus::Connection::session().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            eprintln!("clipboard sync failed: {err:#}");
+            return;
+        }
+    };
+    for device in devices {
+        if require_plugin(&device, "kdeconnect_clipboard").await.is_err() {
+            continue;
+        }
+        if let Err(err) = send_clipboard(&conn, &device, content).await {
+            eprintln!("clipboard sync to {device} failed: {err:#}");
+        }
+    }
+}
+
+async fn send_clipboard(conn: &zbus::Connection, device: &str, content: &str) -> Result<()> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{device}/clipboard"))?
+        .interface(CLIPBOARD_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    if proxy.call_method("sendClipboard", &(content,)).await.is_ok() {
+        return Ok(());
+    }
+    // kdeconnectd before 23.08 only knows the argument-less variant, which sends its own view
+    // of the clipboard.
+    proxy
+        .call_method("sendClipboard", &())
+        .await
+        .context("clipboard plugin rejected the update")?;
+    Ok(())
+}
+
+/// Current text selection, skipping secrets, non-text content and payloads over `max_bytes`.
+async fn read_text_selection(env: &[(String, String)], max_bytes: usize) -> Option<String> {
+    let types = Command::new("wl-paste")
+        .arg("--list-types")
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    let types = String::from_utf8_lossy(&types.stdout);
+    if types.lines().any(|t| t.trim() == PASSWORD_HINT_MIME) {
+        return None;
+    }
+    if !types.lines().any(|t| t.starts_with("text/") || t == "UTF8_STRING" || t == "STRING") {
+        return None;
+    }
+
+    let output = Command::new("wl-paste")
+        .args(["--no-newline", "--type", "text"])
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() || output.stdout.is_empty() || output.stdout.len() > max_bytes {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use hyprconnect_core::{user_data_dir, Contact, ContactsAction, IpcResponse};
+use tokio::time::{timeout, Duration};
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+
+use crate::{into_data_response, into_response, require_plugin, resolve_device, Shared};
+
+const CONTACTS_IFACE: &str = "org.kde.kdeconnect.device.contacts";
+
+/// Parsed contacts per device, loaded lazily from `$XDG_DATA_HOME/hyprconnect/contacts/<device>/`.
+#[derive(Default)]
+pub struct ContactBook {
+    cache: HashMap<String, Vec<Contact>>,
+}
+
+impl ContactBook {
+    async fn load(&mut self, device: &str) -> Result<&Vec<Contact>> {
+        if !self.cache.contains_key(device) {
+            let contacts = read_store(&store_dir(device)?).await?;
+            self.cache.insert(device.to_string(), contacts);
+        }
+        Ok(self.cache.get(device).expect("contacts entry was just inserted"))
+    }
+
+    /// Resolve a contact name to its first phone number in `device`'s address book. An exact
+    /// (case-insensitive) name match wins; otherwise the name must be part of exactly one contact.
+    pub async fn number_for(&mut self, device: &str, name: &str) -> Result<String> {
+        let contacts = self.load(device).await?;
+        let wanted = name.trim().to_lowercase();
+        let with_phone = || contacts.iter().filter(|c| !c.phones.is_empty());
+        let mut matches: Vec<&Contact> = with_phone()
+            .filter(|c| c.name.to_lowercase() == wanted)
+            .collect();
+        if matches.is_empty() {
+            matches = with_phone()
+                .filter(|c| c.name.to_lowercase().contains(&wanted))
+                .collect();
+        }
+        match matches.as_slice() {
+            [contact] => Ok(contact.phones[0].clone()),
+            [] => Err(anyhow!(
+                "no contact with a phone number matches '{name}'; run `hyprconnectctl contacts sync` if the address book is out of date"
+            )),
+            several => {
+                let names: Vec<&str> = several.iter().map(|c| c.name.as_str()).collect();
+                Err(anyhow!(
+                    "'{name}' matches several contacts ({}); use a fuller name or the number",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+
+    /// Resolve a phone number to a contact name, preferring `device`'s address book.
+    pub async fn lookup(&mut self, device: &str, number: &str) -> Option<String> {
+        let wanted = normalize_number(number)?;
+        if let Ok(contacts) = self.load(device).await {
+            if let Some(name) = find_by_number(contacts, &wanted) {
+                return Some(name);
+            }
+        }
+        for contacts in self.cache.values() {
+            if let Some(name) = find_by_number(contacts, &wanted) {
+                return Some(name);
+            }
+        }
+        None
+    }
+}
+
+pub async fn handle_action(
+    shared: &Shared,
+    device: Option<String>,
+    action: ContactsAction,
+) -> IpcResponse {
+    match action {
+        ContactsAction::Sync => {
+            let result = match resolve_device(shared, device).await {
+                Ok(dev) => sync(shared, &dev)
+                    .await
+                    .map(|count| format!("Synced {count} contacts from {dev}")),
+                Err(err) => Err(err),
+            };
+            into_response(result)
+        }
+        ContactsAction::List => into_data_response(contacts_for(shared, device).await),
+        ContactsAction::Search { query } => {
+            let query = query.to_lowercase();
+            let digits = normalize_number(&query);
+            let result = contacts_for(shared, device).await.map(|contacts| {
+                contacts
+                    .into_iter()
+                    .filter(|c| {
+                        c.name.to_lowercase().contains(&query)
+                            || c.emails.iter().any(|e| e.to_lowercase().contains(&query))
+                            || digits.as_ref().is_some_and(|d| {
+                                c.phones
+                                    .iter()
+                                    .filter_map(|p| normalize_number(p))
+                                    .any(|p| p.contains(d.as_str()))
+                            })
+                    })
+                    .collect::<Vec<_>>()
+            });
+            into_data_response(result)
+        }
+        ContactsAction::Export => into_data_response(export(shared, device).await),
+    }
+}
+
+/// Contacts of one device, or of every synced device when none is given.
+async fn contacts_for(shared: &Shared, device: Option<String>) -> Result<Vec<Contact>> {
+    let devices = match device {
+        Some(device) => vec![device],
+        None => synced_devices().await?,
+    };
+    let mut book = shared.contacts.lock().await;
+    let mut out = Vec::new();
+    for device in devices {
+        out.extend(book.load(&device).await?.iter().cloned());
+    }
+    out.sort_by_key(|c| c.name.to_lowercase());
+    Ok(out)
+}
+
+async fn export(shared: &Shared, device: Option<String>) -> Result<String> {
+    let devices = match device {
+        Some(device) => vec![device],
+        None => synced_devices().await?,
+    };
+    let _guard = shared.contacts.lock().await;
+    let mut out = String::new();
+    for device in devices {
+        for path in vcard_files(&store_dir(&device)?).await? {
+            let raw = tokio::fs::read_to_string(&path).await?;
+            out.push_str(raw.trim_end());
+            out.push_str("\r\n");
+        }
+    }
+    if out.is_empty() {
+        return Err(anyhow!("no synced contacts; run `hyprconnectctl contacts sync` first"));
+    }
+    Ok(out)
+}
+
+/// Ask the phone for its address book, then mirror kdeconnectd's vCard cache into our store.
+pub async fn sync(shared: &Shared, device: &str) -> Result<usize> {
+    require_plugin(device, "kdeconnect_contacts").await?;
+
+    let conn = zbus::Connection::session().await?;
+    let path = format!("/modules/kdeconnect/devices/{device}/contacts");
+    let rule = zbus::MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .path(path.clone())?
+        .interface(CONTACTS_IFACE)?
+        .member("localCacheSynchronized")?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &conn, None).await?;
+
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+        .destination("org.kde.kdeconnect")?
+        .path(path)?
+        .interface(CONTACTS_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    proxy
+        .call_method("synchronizeRemoteWithLocal", &())
+        .await
+        .context("contacts plugin rejected the sync request")?;
+
+    // Large address books take a while; fall back to whatever kdeconnectd has cached.
+    let _ = timeout(Duration::from_secs(20), stream.next()).await;
+
+    let source = kdeconnect_vcard_dir(device)?;
+    let target = store_dir(device)?;
+    tokio::fs::create_dir_all(&target)
+        .await
+        .with_context(|| format!("failed to create contacts dir: {}", target.display()))?;
+
+    let mut book = shared.contacts.lock().await;
+    let mut copied = HashSet::new();
+    for file in vcard_files(&source).await? {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        tokio::fs::copy(&file, target.join(name))
+            .await
+            .with_context(|| format!("failed to copy {}", file.display()))?;
+        copied.insert(name.to_os_string());
+    }
+    for stale in vcard_files(&target).await? {
+        if stale.file_name().is_some_and(|n| !copied.contains(n)) {
+            let _ = tokio::fs::remove_file(&stale).await;
+        }
+    }
+
+    book.cache.remove(device);
+    Ok(book.load(device).await?.len())
+}
+
+async fn synced_devices() -> Result<Vec<String>> {
+    let root = contacts_root()?;
+    let mut devices = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(&root).await else {
+        return Ok(devices);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            devices.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(devices)
+}
+
+async fn read_store(dir: &PathBuf) -> Result<Vec<Contact>> {
+    let mut contacts = Vec::new();
+    for path in vcard_files(dir).await? {
+        let raw = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+        contacts.extend(parse_vcards(&raw));
+    }
+    Ok(contacts)
+}
+
+async fn vcard_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Ok(files);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("vcf")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Minimal vCard 2.1/3.0/4.0 reader: FN/N, TEL, EMAIL and the kdeconnect UID.
+fn parse_vcards(raw: &str) -> Vec<Contact> {
+    // Unfold continuation lines (RFC 6350 §3.2).
+    let mut lines: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    let mut contacts = Vec::new();
+    let mut current: Option<Contact> = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = key.split(';').next().unwrap_or_default().to_ascii_uppercase();
+        // Strip grouping prefixes such as `item1.TEL`.
+        let name = name.rsplit('.').next().unwrap_or_default();
+        let value = value.trim().replace("\\,", ",").replace("\\;", ";");
+
+        match name {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => {
+                current = Some(Contact {
+                    uid: String::new(),
+                    name: String::new(),
+                    phones: Vec::new(),
+                    emails: Vec::new(),
+                });
+            }
+            "END" if value.eq_ignore_ascii_case("VCARD") => {
+                if let Some(mut contact) = current.take() {
+                    if contact.name.is_empty() {
+                        contact.name = contact.phones.first().cloned().unwrap_or_default();
+                    }
+                    if !contact.name.is_empty() {
+                        contacts.push(contact);
+                    }
+                }
+            }
+            _ => {
+                let Some(contact) = current.as_mut() else {
+                    continue;
+                };
+                match name {
+                    "FN" => contact.name = value,
+                    "N" if contact.name.is_empty() => {
+                        let parts: Vec<&str> = value.split(';').collect();
+                        let given = parts.get(1).copied().unwrap_or_default();
+                        let family = parts.first().copied().unwrap_or_default();
+                        contact.name = format!("{given} {family}").trim().to_string();
+                    }
+                    "TEL" if !value.is_empty() => contact.phones.push(value),
+                    "EMAIL" if !value.is_empty() => contact.emails.push(value),
+                    "UID" | "X-KDECONNECT-ID-DEV" if contact.uid.is_empty() => contact.uid = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+    contacts
+}
+
+/// Digits of a phone number, or `None` when the input does not look like one.
+pub fn normalize_number(raw: &str) -> Option<String> {
+    let looks_like_number = raw
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | ' ' | '-' | '(' | ')' | '.'));
+    let digits: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+    if !looks_like_number || digits.len() < 3 {
+        return None;
+    }
+    Some(digits)
+}
+
+/// Numbers match when equal, or when their last 9 digits agree (ignores country/trunk prefixes).
+fn find_by_number(contacts: &[Contact], wanted: &str) -> Option<String> {
+    contacts
+        .iter()
+        .find(|c| {
+            c.phones
+                .iter()
+                .filter_map(|p| normalize_number(p))
+                .any(|p| numbers_match(&p, wanted))
+        })
+        .map(|c| c.name.clone())
+}
+
+fn numbers_match(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.len() < 7 || b.len() < 7 {
+        return false;
+    }
+    let tail = |s: &str| s[s.len().saturating_sub(9)..].to_string();
+    tail(a) == tail(b)
+}
+
+fn contacts_root() -> Result<PathBuf> {
+    let base = user_data_dir()?;
+    Ok(base.join("hyprconnect").join("contacts"))
+}
+
+fn store_dir(device: &str) -> Result<PathBuf> {
+    Ok(contacts_root()?.join(device))
+}
+
+/// Where kdeconnectd's contacts plugin writes the vCards it receives.
+fn kdeconnect_vcard_dir(device: &str) -> Result<PathBuf> {
+    let base = user_data_dir()?;
+    Ok(base.join("kpeoplevcard").join(format!("kdeconnect-{device}")))
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use hyprconnect_core::{build_waybar_payload, state_export_dir, DaemonState, StateExport};
+
+/// Write `state.json` (and `state.txt` when a template is configured) into the runtime dir.
+pub async fn write_state_files(state: &DaemonState, template: Option<&str>) -> Result<()> {
+    let dir = state_export_dir();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed to create state dir: {}", dir.display()))?;
+
+    let export = StateExport {
+        state: state.clone(),
+        waybar: build_waybar_payload(state),
+    };
+    let body = serde_json::to_vec_pretty(&export)?;
+    write_atomic(&dir.join("state.json"), &body).await?;
+
+    if let Some(template) = template.filter(|t| !t.is_empty()) {
+        let mut text = render_template(template, &export);
+        text.push('\n');
+        write_atomic(&dir.join("state.txt"), text.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+pub async fn write_atomic(path: &Path, body: &[u8]) -> Result<()> {
+    // Unique temp name so overlapping refreshes never rename each other's half-written file.
+    let seq = TMP_SEQ.fetch_add(1, Ordering::Relaxed);
+    let tmp = path.with_extension(format!("tmp.{}.{seq}", std::process::id()));
+    tokio::fs::write(&tmp, body)
+        .await
+        .with_context(|| format!("failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Expand `{placeholder}` tokens against the first reachable device (or the first known one).
+fn render_template(template: &str, export: &StateExport) -> String {
+    let device = export
+        .state
+        .devices
+        .iter()
+        .find(|d| d.reachable)
+        .or_else(|| export.state.devices.first());
+
+    let or_dash = |v: Option<String>| v.unwrap_or_else(|| "--".to_string());
+    let (name, id, status, battery, charging, signal, network, mounted) = match device {
+        Some(d) => (
+            d.name.clone(),
+            d.id.clone(),
+            if d.reachable { "connected" } else { "offline" }.to_string(),
+            or_dash(d.battery_percent.map(|v| v.to_string())),
+            if d.charging == Some(true) { "yes" } else { "no" }.to_string(),
+            or_dash(d.signal_percent.map(|v| v.to_string())),
+            or_dash(d.network_type.clone()),
+            if d.mounted { "yes" } else { "no" }.to_string(),
+        ),
+        None => (
+            "--".to_string(),
+            "--".to_string(),
+            "offline".to_string(),
+            "--".to_string(),
+            "no".to_string(),
+            "--".to_string(),
+            "--".to_string(),
+            "no".to_string(),
+        ),
+    };
+
+    template
+        .replace("{name}", &name)
+        .replace("{id}", &id)
+        .replace("{status}", &status)
+        .replace("{battery}", &battery)
+        .replace("{charging}", &charging)
+        .replace("{signal}", &signal)
+        .replace("{network}", &network)
+        .replace("{mounted}", &mounted)
+        .replace("{text}", &export.waybar.text)
+        .replace("{class}", &export.waybar.class)
+}
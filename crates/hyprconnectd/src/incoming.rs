@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use hyprconnect_core::rules::HYPRCONNECT_APP;
+use hyprconnect_core::{expand_home, NotificationUrgency, UrlOpenPolicy};
+use notify_rust::Notification;
+use tokio::process::Command;
+use zbus::proxy::CacheProperties;
+
+use crate::notifications::{desktop_urgency, ActionTarget};
+use crate::share::percent_decode;
+use crate::{notify_local, Shared};
+
+/// Handle the share plugin's `shareReceived(url)`: route received files according to
+/// `incoming_routes` and apply `received_url_policy` to shared links.
+pub async fn handle_share_received(shared: &Shared, device: &str, url: &str) -> Result<()> {
+    let name = device_name(shared, device).await;
+    match url.strip_prefix("file://") {
+        Some(path) => {
+            let path = PathBuf::from(percent_decode(path));
+            if !path.is_file() {
+                return Ok(());
+            }
+            let path = route(shared, &path).await?;
+            notify_file(shared, &name, &path).await;
+            Ok(())
+        }
+        None => handle_url(shared, &name, url).await,
+    }
+}
+
+/// Move `path` to the first matching route's destination; returns where the file ended up.
+async fn route(shared: &Shared, path: &Path) -> Result<PathBuf> {
+    if shared.config.incoming_routes.is_empty() {
+        return Ok(path.to_path_buf());
+    }
+    let mime = detect_mime(path).await;
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+    let Some(route) = shared
+        .config
+        .incoming_routes
+        .iter()
+        .find(|r| r.matches(mime.as_deref(), extension.as_deref()))
+    else {
+        return Ok(path.to_path_buf());
+    };
+
+    let dir = expand_home(&route.destination)?;
+    if path.parent() == Some(dir.as_path()) {
+        return Ok(path.to_path_buf());
+    }
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("received path has no file name: {}", path.display()))?;
+    let target = unique_path(&dir, &file_name.to_string_lossy());
+    move_file(path, &target).await?;
+    Ok(target)
+}
+
+/// `name`, or `name (1)`, `name (2)`, ... before the extension, whichever does not exist yet.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{stem} ({n}){extension}")))
+        .find(|p| !p.exists())
+        .expect("unbounded counter yields a free name")
+}
+
+async fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    // Destinations on another filesystem cannot be renamed into.
+    tokio::fs::copy(from, to)
+        .await
+        .with_context(|| format!("failed to copy {} to {}", from.display(), to.display()))?;
+    tokio::fs::remove_file(from)
+        .await
+        .with_context(|| format!("failed to remove {}", from.display()))?;
+    Ok(())
+}
+
+async fn detect_mime(path: &Path) -> Option<String> {
+    let output = Command::new("file")
+        .args(["--brief", "--mime-type"])
+        .arg(path)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    let mime = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && mime.contains('/')).then_some(mime)
+}
+
+async fn notify_file(shared: &Shared, device_name: &str, path: &Path) {
+    if !shared.config.notifications_enabled || !shared.config.incoming_notifications_enabled {
+        return;
+    }
+    let summary = format!("File received from {device_name}");
+    let body = path.display().to_string();
+    let target = ActionTarget::ReceivedFile {
+        path: path.to_path_buf(),
+    };
+    show_with_actions(
+        shared,
+        &summary,
+        &body,
+        &[("open", "Open"), ("show", "Show in folder")],
+        target,
+    )
+    .await;
+}
+
+async fn handle_url(shared: &Shared, device_name: &str, url: &str) -> Result<()> {
+    let policy = shared.config.received_url_policy;
+    if policy == UrlOpenPolicy::Always {
+        open(url)?;
+    }
+    if !shared.config.notifications_enabled || !shared.config.incoming_notifications_enabled {
+        return Ok(());
+    }
+    let summary = format!("Link received from {device_name}");
+    match policy {
+        UrlOpenPolicy::Ask => {
+            let target = ActionTarget::ReceivedUrl {
+                url: url.to_string(),
+            };
+            show_with_actions(shared, &summary, url, &[("open", "Open")], target).await;
+        }
+        UrlOpenPolicy::Always | UrlOpenPolicy::Never => {
+            notify_local(shared, &summary, url, NotificationUrgency::Low);
+        }
+    }
+    Ok(())
+}
+
+async fn show_with_actions(
+    shared: &Shared,
+    summary: &str,
+    body: &str,
+    actions: &[(&str, &str)],
+    target: ActionTarget,
+) {
+    let decision = shared.config.notification_rules.evaluate(
+        HYPRCONNECT_APP,
+        summary,
+        body,
+        NotificationUrgency::Low,
+        Local::now(),
+    );
+    if !decision.show {
+        return;
+    }
+    let mut notification = Notification::new();
+    notification
+        .appname(HYPRCONNECT_APP)
+        .summary(summary)
+        .body(body)
+        .urgency(desktop_urgency(decision.urgency));
+    for (id, label) in actions {
+        notification.action(id, label);
+    }
+    let Ok(handle) = notification.show() else {
+        return;
+    };
+    shared.mirror.lock().await.register_actions(handle.id(), target);
+}
+
+/// Open a file or URL with the desktop's default handler.
+pub fn open(target: &str) -> Result<()> {
+    Command::new("xdg-open")
+        .arg(target)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("failed to spawn xdg-open")?;
+    Ok(())
+}
+
+/// Reveal `path` through `org.freedesktop.FileManager1`, falling back to opening its folder.
+pub async fn show_in_folder(conn: &zbus::Connection, path: &Path) -> Result<()> {
+    let uri = file_uri(path);
+    let shown = async {
+        let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+            .destination("org.freedesktop.FileManager1")?
+            .path("/org/freedesktop/FileManager1")?
+            .interface("org.freedesktop.FileManager1")?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        proxy.call_method("ShowItems", &(vec![uri], "")).await?;
+        anyhow::Ok(())
+    }
+    .await;
+    if shown.is_ok() {
+        return Ok(());
+    }
+    let dir = path.parent().unwrap_or(path);
+    open(&dir.to_string_lossy())
+}
+
+async fn device_name(shared: &Shared, device: &str) -> String {
+    let state = shared.state.read().await;
+    state
+        .devices
+        .iter()
+        .find(|d| d.id == device && !d.name.is_empty())
+        .map(|d| d.name.clone())
+        .unwrap_or_else(|| device.to_string())
+}
+
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
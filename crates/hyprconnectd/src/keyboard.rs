@@ -0,0 +1,46 @@
+use anyhow::{anyhow, Context, Result};
+use hyprconnect_core::{KeyPress, SpecialKey};
+use zbus::proxy::CacheProperties;
+
+use crate::{require_plugin, resolve_device, Shared};
+
+pub const REMOTE_KEYBOARD_IFACE: &str = "org.kde.kdeconnect.device.remotekeyboard";
+
+/// Send `keys` to the phone's focused field, in order.
+pub async fn send_keys(shared: &Shared, device: Option<String>, keys: &[KeyPress]) -> Result<String> {
+    if keys.is_empty() {
+        return Err(anyhow!("no keys to send"));
+    }
+    let dev = resolve_device(shared, device).await?;
+    require_plugin(&dev, "kdeconnect_remotekeyboard").await?;
+
+    // Called directly rather than through busctl so typed text is never parsed as a flag.
+    let conn = zbus::Connection::session().await?;
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{dev}/remotekeyboard"))?
+        .interface(REMOTE_KEYBOARD_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // The phone only accepts input while its KDE Connect keyboard is the active input method.
+    if let Ok(false) = proxy.get_property::<bool>("remoteState").await {
+        return Err(anyhow!(
+            "the KDE Connect remote keyboard is not active on {dev}; select it as the phone's input method"
+        ));
+    }
+
+    for key in keys {
+        let code = key.special.map_or(0, SpecialKey::code);
+        let text = if key.special.is_some() { "" } else { key.text.as_str() };
+        proxy
+            .call_method(
+                "sendKeyPress",
+                &(text, code, key.shift, key.ctrl, key.alt, false),
+            )
+            .await
+            .context("failed to send key press")?;
+    }
+    Ok(format!("Sent {} key event(s) to {dev}", keys.len()))
+}
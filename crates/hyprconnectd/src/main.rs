@@ -5,32 +5,89 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::{anyhow, Context, Result};
-use chrono::Utc;
+use chrono::{Local, Utc};
 use futures_util::StreamExt;
+use hyprconnect_core::rules::HYPRCONNECT_APP;
 use hyprconnect_core::{
-    runtime_socket_path, Config, DaemonState, DeviceState, IpcRequest, IpcResponse, MediaAction,
+    runtime_socket_path, Config, DaemonState, DeviceState, IpcRequest, IpcResponse,
+    NotificationUrgency,
 };
 use notify_rust::Notification;
 use regex::Regex;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::process::Command;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tokio::time::{sleep, Duration};
 use zbus::message::Type as MessageType;
 
+mod audit;
+mod calls;
+mod clipboard;
+mod contacts;
+mod export;
+mod incoming;
+mod keyboard;
+mod media;
+mod metrics;
+mod notification_history;
+mod notifications;
+mod queue;
+mod share;
+mod sms;
+mod transfers;
+
+use audit::AuditLog;
+use calls::CallTracker;
+use clipboard::ClipboardSync;
+use contacts::ContactBook;
+use media::MediaTracker;
+use metrics::Metrics;
+use notification_history::NotificationHistory;
+use notifications::Mirror;
+use queue::ActionQueue;
+use sms::SmsTracker;
+use transfers::TransferTracker;
+
 #[derive(Clone)]
 struct Shared {
     state: Arc<RwLock<DaemonState>>,
     config: Config,
+    metrics: Arc<Metrics>,
+    mirror: Arc<Mutex<Mirror>>,
+    history: Arc<Mutex<NotificationHistory>>,
+    sms: Arc<Mutex<SmsTracker>>,
+    calls: Arc<Mutex<CallTracker>>,
+    contacts: Arc<Mutex<ContactBook>>,
+    clipboard: Arc<Mutex<ClipboardSync>>,
+    transfers: Arc<Mutex<TransferTracker>>,
+    queue: Arc<Mutex<ActionQueue>>,
+    audit: Arc<Mutex<AuditLog>>,
+    media: Arc<Mutex<MediaTracker>>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load().unwrap_or_default();
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{err:#}; using default settings");
+        Config::default()
+    });
     let shared = Shared {
         state: Arc::new(RwLock::new(DaemonState::default())),
         config: config.clone(),
+        metrics: Arc::new(Metrics::default()),
+        mirror: Arc::new(Mutex::new(Mirror::default())),
+        history: Arc::new(Mutex::new(NotificationHistory::new(
+            config.notification_history_limit,
+        ))),
+        sms: Arc::new(Mutex::new(SmsTracker::default())),
+        calls: Arc::new(Mutex::new(CallTracker::default())),
+        contacts: Arc::new(Mutex::new(ContactBook::default())),
+        clipboard: Arc::new(Mutex::new(ClipboardSync::default())),
+        transfers: Arc::new(Mutex::new(TransferTracker::default())),
+        queue: Arc::new(Mutex::new(ActionQueue::default())),
+        audit: Arc::new(Mutex::new(AuditLog::default())),
+        media: Arc::new(Mutex::new(MediaTracker::default())),
     };
 
     let socket = runtime_socket_path()?;
@@ -50,6 +107,16 @@ async fn main() -> Result<()> {
         }
     });
 
+    let actions = shared.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = notifications::listen_for_actions(actions.clone()).await {
+                eprintln!("notification action listener failed: {err:#}");
+                sleep(Duration::from_secs(2)).await;
+            }
+        }
+    });
+
     let events = shared.clone();
     tokio::spawn(async move {
         loop {
@@ -60,6 +127,35 @@ async fn main() -> Result<()> {
         }
     });
 
+    let jobs = shared.clone();
+    tokio::spawn(async move {
+        if let Err(err) = transfers::serve(jobs).await {
+            eprintln!("transfer tracking unavailable: {err:#}");
+        }
+    });
+
+    if shared.config.clipboard_sync_enabled {
+        let watcher = shared.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = clipboard::watch(watcher.clone()).await {
+                    eprintln!("clipboard watcher failed: {err:#}");
+                    sleep(Duration::from_secs(5)).await;
+                }
+            }
+        });
+    }
+
+    if let Some(listen) = shared.config.metrics_listen.clone().filter(|v| !v.is_empty()) {
+        let metrics = shared.metrics.clone();
+        let state = shared.state.clone();
+        tokio::spawn(async move {
+            if let Err(err) = metrics::serve(&listen, metrics, state).await {
+                eprintln!("metrics listener failed: {err:#}");
+            }
+        });
+    }
+
     if let Err(err) = refresh_state(&shared).await {
         eprintln!("initial refresh failed: {err:#}");
     }
@@ -78,29 +174,79 @@ async fn main() -> Result<()> {
 async fn handle_stream(mut stream: UnixStream, shared: Shared) -> Result<()> {
     let mut buf = Vec::new();
     stream.read_to_end(&mut buf).await?;
-    let req: IpcRequest = serde_json::from_slice(&buf).context("invalid IPC request JSON")?;
+    let req = match serde_json::from_slice::<IpcRequest>(&buf) {
+        Ok(req) => req,
+        Err(err) => {
+            // Client-supplied type strings never become label values.
+            shared.metrics.record_ipc_request("invalid");
+            return Err(err).context("invalid IPC request JSON");
+        }
+    };
+    shared.metrics.record_ipc_request(req.kind());
+
+    let resp = handle_request(&shared, req).await?;
+
+    let body = serde_json::to_vec(&resp)?;
+    stream.write_all(&body).await?;
+    Ok(())
+}
+
+/// Handle one IPC request and record it in the audit log. Also used to replay queued and
+/// re-sent actions.
+async fn handle_request(shared: &Shared, req: IpcRequest) -> Result<IpcResponse> {
+    let audited = audit::is_audited(&req).then(|| req.clone());
+    let result = dispatch(shared, req).await;
+    if let Some(req) = audited {
+        audit::record(shared, req, &result).await;
+    }
+    result
+}
 
+async fn dispatch(shared: &Shared, req: IpcRequest) -> Result<IpcResponse> {
     let resp = match req {
         IpcRequest::GetState => IpcResponse {
             ok: true,
             message: None,
             state: Some(shared.state.read().await.clone()),
+            data: None,
         },
         IpcRequest::ShareFile { path, device } => {
-            let result = share_path(&shared, &path, device).await;
+            let result = share_path(shared, &path, device).await;
             into_response(result)
         }
         IpcRequest::ShareUrl { url, device } => {
-            let result = share_path(&shared, &url, device).await;
+            let result = share_path(shared, &url, device).await;
+            into_response(result)
+        }
+        IpcRequest::ShareFiles { paths, device } => {
+            let result = match resolve_device(shared, device).await {
+                Ok(dev) => Ok(share::share_files(&dev, &paths).await),
+                Err(err) => Err(err),
+            };
+            into_data_response(result)
+        }
+        IpcRequest::ShareText { text, device } => {
+            let result = match resolve_device(shared, device).await {
+                Ok(dev) if !text.trim().is_empty() => share::share_text(&dev, &text)
+                    .await
+                    .map(|_| format!("Shared text to {dev}")),
+                Ok(_) => Err(anyhow!("text is empty")),
+                Err(err) => Err(err),
+            };
             into_response(result)
         }
-        IpcRequest::ShareClipboard { device } => {
-            let clip = read_clipboard().await?;
-            let result = share_path(&shared, &clip, device).await;
+        IpcRequest::ShareClipboard {
+            device,
+            capture,
+            primary,
+            wayland_env,
+        } => {
+            let result =
+                share::share_clipboard(shared, device, capture, primary, &wayland_env).await;
             into_response(result)
         }
         IpcRequest::Ping { message, device } => {
-            let dev = resolve_device(&shared, device).await?;
+            let dev = resolve_device(shared, device).await?;
             let ping_msg = message.unwrap_or_else(|| "Ping from Hyprconnect".to_string());
             let result = run_kdeconnect(&[
                 "--device",
@@ -125,7 +271,7 @@ async fn handle_stream(mut stream: UnixStream, shared: Shared) -> Result<()> {
             into_response(result)
         }
         IpcRequest::Find { device } => {
-            let dev = resolve_device(&shared, device).await?;
+            let dev = resolve_device(shared, device).await?;
             let result = run_kdeconnect(&["--device", &dev, "--ring"])
                 .await
                 .map(|_| format!("Ringing {dev}"));
@@ -138,32 +284,64 @@ async fn handle_stream(mut stream: UnixStream, shared: Shared) -> Result<()> {
             into_response(result)
         }
         IpcRequest::Mount { device } => {
-            let dev = resolve_device(&shared, device).await?;
+            let dev = resolve_device(shared, device).await?;
             let result = mount_device(&dev)
                 .await
                 .map(|mount| format!("Mounted {dev} at {mount}"));
             into_response(result)
         }
         IpcRequest::OpenMount { device } => {
-            let dev = resolve_device(&shared, device).await?;
+            let dev = resolve_device(shared, device).await?;
             let result = open_device_mount(&dev)
                 .await
                 .map(|mount| format!("Opened mount for {dev}: {mount}"));
             into_response(result)
         }
         IpcRequest::ToggleMount { device } => {
-            let result = toggle_mount(&shared, device).await;
+            let result = toggle_mount(shared, device).await;
+            into_response(result)
+        }
+        IpcRequest::Media {
+            device,
+            auto_target,
+            action,
+        } => media::handle_action(shared, device, auto_target, action).await,
+        IpcRequest::MediaPauseAll => into_response(media::pause_all(shared).await),
+        IpcRequest::Notifications { device, action } => {
+            notifications::handle_action(shared, device, action).await
+        }
+        IpcRequest::SendSms {
+            device,
+            recipients,
+            body,
+            attachments,
+        } => {
+            let result = sms::send_sms(shared, device, &recipients, &body, &attachments).await;
             into_response(result)
         }
-        IpcRequest::Media { device, action } => {
-            let result = handle_media_action(&shared, device, action).await;
+        IpcRequest::Sms { device, action } => sms::handle_action(shared, device, action).await,
+        IpcRequest::SendKeys { device, keys } => {
+            into_response(keyboard::send_keys(shared, device, &keys).await)
+        }
+        IpcRequest::EndCall { device } => {
+            let result = end_call(shared, device).await;
+            into_response(result)
+        }
+        IpcRequest::Contacts { device, action } => {
+            contacts::handle_action(shared, device, action).await
+        }
+        IpcRequest::Transfers => {
+            let transfers = shared.transfers.lock().await.list();
+            into_data_response(Ok(transfers))
+        }
+        IpcRequest::ClipboardSync { action } => {
+            let result = clipboard::handle_action(shared, action).await;
             into_response(result)
         }
+        IpcRequest::Queue { action } => queue::handle_action(shared, action).await,
+        IpcRequest::History { action } => audit::handle_action(shared, action).await,
     };
-
-    let body = serde_json::to_vec(&resp)?;
-    stream.write_all(&body).await?;
-    Ok(())
+    Ok(resp)
 }
 
 fn into_response(result: Result<String>) -> IpcResponse {
@@ -172,21 +350,55 @@ fn into_response(result: Result<String>) -> IpcResponse {
             ok: true,
             message: Some(message),
             state: None,
+            data: None,
         },
         Err(err) => IpcResponse {
             ok: false,
             message: Some(err.to_string()),
             state: None,
+            data: None,
         },
     }
 }
 
+fn into_data_response<T: serde::Serialize>(result: Result<T>) -> IpcResponse {
+    match result.and_then(|v| Ok(serde_json::to_value(v)?)) {
+        Ok(data) => IpcResponse {
+            ok: true,
+            message: None,
+            state: None,
+            data: Some(data),
+        },
+        Err(err) => into_response(Err(err)),
+    }
+}
+
 async fn refresh_state(shared: &Shared) -> Result<()> {
+    let result = apply_refresh(shared).await;
+    if result.is_err() {
+        shared.metrics.record_refresh_failure();
+    }
+    write_metrics_textfile(shared).await;
+    result
+}
+
+async fn write_metrics_textfile(shared: &Shared) {
+    let Some(path) = shared.config.metrics_textfile.as_deref().filter(|p| !p.is_empty()) else {
+        return;
+    };
+    let state = shared.state.read().await.clone();
+    if let Err(err) = metrics::write_textfile(Path::new(path), &shared.metrics, &state).await {
+        eprintln!("metrics textfile write failed: {err:#}");
+    }
+}
+
+async fn apply_refresh(shared: &Shared) -> Result<()> {
     if !command_exists("kdeconnect-cli").await {
         let next = DaemonState {
             devices: Vec::new(),
             updated_at: Some(Utc::now()),
         };
+        export_state(shared, &next).await;
         *shared.state.write().await = next;
         return Ok(());
     }
@@ -212,6 +424,7 @@ async fn refresh_state(shared: &Shared) -> Result<()> {
             charging,
             signal_percent,
             network_type,
+            in_call: false,
         });
     }
 
@@ -230,49 +443,181 @@ async fn refresh_state(shared: &Shared) -> Result<()> {
             charging: None,
             signal_percent: None,
             network_type: None,
+            in_call: false,
         });
     }
 
+    // Telephony has no call-ended signal for answered calls; a phone that drops off ends it.
+    let dropped: Vec<String> = {
+        let tracker = shared.calls.lock().await;
+        devices
+            .iter()
+            .filter(|d| !d.reachable && tracker.in_call(&d.id))
+            .map(|d| d.id.clone())
+            .collect()
+    };
+    if !dropped.is_empty() {
+        let conn = zbus::Connection::session().await?;
+        for id in &dropped {
+            calls::end_call(shared, &conn, id).await;
+        }
+    }
+    {
+        let tracker = shared.calls.lock().await;
+        for d in &mut devices {
+            d.in_call = tracker.in_call(&d.id);
+        }
+    }
+
     let next = DaemonState {
         devices,
         updated_at: Some(Utc::now()),
     };
 
     maybe_notify_connection_changes(shared, &prev, &next)?;
+    if shared.config.contacts_sync_enabled {
+        sync_contacts_on_connect(shared, &prev, &next);
+    }
+    let connected: Vec<String> = next
+        .devices
+        .iter()
+        .filter(|d| d.reachable && d.paired)
+        .filter(|d| !prev.devices.iter().any(|o| o.id == d.id && o.reachable))
+        .map(|d| d.id.clone())
+        .collect();
+    let reachability_changed: Vec<String> = next
+        .devices
+        .iter()
+        .filter(|d| {
+            let was = prev.devices.iter().any(|o| o.id == d.id && o.reachable);
+            d.reachable != was
+        })
+        .map(|d| d.id.clone())
+        .collect();
+    export_state(shared, &next).await;
     *shared.state.write().await = next;
+    media::reachability_changed(shared, &reachability_changed).await;
+
+    // Delivery resolves devices through the state, so it runs after the update.
+    queue::expire(shared).await;
+    for id in connected {
+        let shared = shared.clone();
+        tokio::spawn(async move { queue::deliver(&shared, &id).await });
+    }
     Ok(())
 }
 
+async fn export_state(shared: &Shared, state: &DaemonState) {
+    if !shared.config.state_file_enabled {
+        return;
+    }
+    let template = shared.config.state_text_template.as_deref();
+    if let Err(err) = export::write_state_files(state, template).await {
+        eprintln!("state export failed: {err:#}");
+    }
+}
+
 fn maybe_notify_connection_changes(shared: &Shared, prev: &DaemonState, next: &DaemonState) -> Result<()> {
     if !shared.config.notifications_enabled {
         return Ok(());
     }
 
-    let prev_map: HashMap<&str, bool> = prev
+    let prev_map: HashMap<&str, &DeviceState> = prev
         .devices
         .iter()
-        .map(|d| (d.id.as_str(), d.reachable))
+        .map(|d| (d.id.as_str(), d))
         .collect();
 
     for d in &next.devices {
-        let old = prev_map.get(d.id.as_str()).copied().unwrap_or(false);
-        if old != d.reachable {
+        let old = prev_map.get(d.id.as_str()).copied();
+        let was_reachable = old.map(|o| o.reachable).unwrap_or(false);
+        if was_reachable != d.reachable {
             let body = if d.reachable {
                 "Phone connected"
             } else {
                 "Phone disconnected"
             };
-            let _ = Notification::new()
-                .summary(&d.name)
-                .body(body)
-                .appname("Hyprconnect")
-                .show();
+            notify_local(shared, &d.name, body, NotificationUrgency::Normal);
+        }
+
+        let (Some(old_batt), Some(batt)) = (old.and_then(|o| o.battery_percent), d.battery_percent)
+        else {
+            continue;
+        };
+        if d.charging == Some(true) {
+            continue;
+        }
+        let crit = shared.config.battery_crit_percent;
+        let warn = shared.config.battery_warn_percent;
+        if old_batt >= crit && batt < crit {
+            let body = format!("Battery critical: {batt}%");
+            notify_local(shared, &d.name, &body, NotificationUrgency::Critical);
+        } else if old_batt >= warn && batt < warn {
+            let body = format!("Battery low: {batt}%");
+            notify_local(shared, &d.name, &body, NotificationUrgency::Normal);
         }
     }
 
     Ok(())
 }
 
+/// Refresh the contact store in the background for every device that just became reachable.
+fn sync_contacts_on_connect(shared: &Shared, prev: &DaemonState, next: &DaemonState) {
+    for d in next.devices.iter().filter(|d| d.reachable && d.paired) {
+        let was_reachable = prev
+            .devices
+            .iter()
+            .any(|o| o.id == d.id && o.reachable);
+        if was_reachable {
+            continue;
+        }
+        let shared = shared.clone();
+        let id = d.id.clone();
+        tokio::spawn(async move {
+            if let Err(err) = contacts::sync(&shared, &id).await {
+                eprintln!("contacts sync for {id} failed: {err:#}");
+            }
+        });
+    }
+}
+
+/// Show a Hyprconnect-originated notification after running it through the notification rules.
+fn notify_local(shared: &Shared, summary: &str, body: &str, urgency: NotificationUrgency) {
+    let decision = shared
+        .config
+        .notification_rules
+        .evaluate(HYPRCONNECT_APP, summary, body, urgency, Local::now());
+    if !decision.show {
+        return;
+    }
+    let _ = Notification::new()
+        .summary(summary)
+        .body(body)
+        .appname(HYPRCONNECT_APP)
+        .urgency(notifications::desktop_urgency(decision.urgency))
+        .show();
+}
+
+async fn end_call(shared: &Shared, device: Option<String>) -> Result<String> {
+    let active: Vec<String> = {
+        let state = shared.state.read().await;
+        state
+            .devices
+            .iter()
+            .filter(|d| d.in_call && device.as_ref().is_none_or(|id| *id == d.id))
+            .map(|d| d.id.clone())
+            .collect()
+    };
+    if active.is_empty() {
+        return Err(anyhow!("no call in progress"));
+    }
+    let conn = zbus::Connection::session().await?;
+    for id in &active {
+        calls::end_call(shared, &conn, id).await;
+    }
+    Ok(format!("Marked call ended on {}", active.join(", ")))
+}
+
 async fn share_path(shared: &Shared, value: &str, device: Option<String>) -> Result<String> {
     if value.trim().is_empty() {
         return Err(anyhow!("clipboard is empty"));
@@ -317,26 +662,6 @@ async fn resolve_device(shared: &Shared, requested: Option<String>) -> Result<St
     ))
 }
 
-async fn read_clipboard() -> Result<String> {
-    let output = Command::new("wl-paste")
-        .arg("-n")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .output()
-        .await
-        .context("failed to execute wl-paste")?;
-
-    if !output.status.success() {
-        return Err(anyhow!("failed to read clipboard with wl-paste"));
-    }
-
-    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if text.is_empty() {
-        return Err(anyhow!("clipboard is empty"));
-    }
-    Ok(text)
-}
-
 async fn list_devices() -> Result<Vec<(String, String)>> {
     let out = run_kdeconnect(&["--list-devices", "--id-name-only"]).await?;
     let legacy = Regex::new(r"^-\s*(?P<name>.+):\s*(?P<id>[A-Za-z0-9_-]+)$").unwrap();
@@ -561,7 +886,11 @@ fn is_mountpoint_mounted(path: &str) -> bool {
 
 async fn listen_for_kdeconnect_events(shared: Shared) -> Result<()> {
     let conn = zbus::Connection::session().await?;
-    let mut stream = zbus::MessageStream::from(&conn);
+    let rule = zbus::MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .path_namespace("/modules/kdeconnect/devices")?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &conn, None).await?;
     let mut last_refresh = Instant::now()
         .checked_sub(Duration::from_secs(1))
         .unwrap_or_else(Instant::now);
@@ -590,6 +919,97 @@ async fn listen_for_kdeconnect_events(shared: Shared) -> Result<()> {
             .map(|v| v.to_string())
             .unwrap_or_default();
 
+        if iface == notifications::NOTIFICATIONS_IFACE {
+            let device = path
+                .trim_start_matches("/modules/kdeconnect/devices/")
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let public_id = msg.body().deserialize::<String>().ok();
+            let s = shared.clone();
+            let c = conn.clone();
+            tokio::spawn(async move {
+                if let Err(err) =
+                    notifications::handle_signal(&s, &c, &device, &member, public_id).await
+                {
+                    eprintln!("phone notification handling failed: {err:#}");
+                }
+            });
+            continue;
+        }
+
+        if iface == calls::TELEPHONY_IFACE && member == "callReceived" {
+            let device = path
+                .trim_start_matches("/modules/kdeconnect/devices/")
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let Ok((event, number, contact)) =
+                msg.body().deserialize::<(String, String, String)>()
+            else {
+                continue;
+            };
+            let s = shared.clone();
+            let c = conn.clone();
+            tokio::spawn(async move {
+                if let Err(err) =
+                    calls::handle_signal(&s, &c, &device, &event, &number, &contact).await
+                {
+                    eprintln!("call event handling failed: {err:#}");
+                }
+            });
+            continue;
+        }
+
+        if iface == share::SHARE_IFACE && member == "shareReceived" {
+            let device = path
+                .trim_start_matches("/modules/kdeconnect/devices/")
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let Ok(url) = msg.body().deserialize::<String>() else {
+                continue;
+            };
+            let s = shared.clone();
+            tokio::spawn(async move {
+                if let Err(err) = incoming::handle_share_received(&s, &device, &url).await {
+                    eprintln!("incoming share handling failed: {err:#}");
+                }
+            });
+            continue;
+        }
+
+        if iface == media::MPRIS_REMOTE_IFACE {
+            let device = path
+                .trim_start_matches("/modules/kdeconnect/devices/")
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let s = shared.clone();
+            tokio::spawn(async move { media::handle_signal(&s, &device).await });
+            continue;
+        }
+
+        if iface == sms::CONVERSATIONS_IFACE {
+            let device = path
+                .trim_start_matches("/modules/kdeconnect/devices/")
+                .to_string();
+            let Ok(value) = msg.body().deserialize::<zbus::zvariant::OwnedValue>() else {
+                continue;
+            };
+            let s = shared.clone();
+            tokio::spawn(async move {
+                if let Err(err) = sms::handle_signal(&s, &device, &member, value).await {
+                    eprintln!("sms notification failed: {err:#}");
+                }
+            });
+            continue;
+        }
+
         if !is_refresh_signal(&iface, &member) {
             continue;
         }
@@ -599,6 +1019,7 @@ async fn listen_for_kdeconnect_events(shared: Shared) -> Result<()> {
         }
         last_refresh = Instant::now();
 
+        shared.metrics.record_signal_refresh();
         if let Err(err) = refresh_state(&shared).await {
             eprintln!("event refresh failed: {err:#}");
         }
@@ -620,6 +1041,20 @@ fn is_refresh_signal(interface: &str, member: &str) -> bool {
     false
 }
 
+/// Fail with a readable error unless `plugin` (e.g. `kdeconnect_sms`) is loaded for the device.
+async fn require_plugin(device: &str, plugin: &str) -> Result<()> {
+    let path = format!("/modules/kdeconnect/devices/{device}");
+    let raw = run_busctl_get_property(&path, "org.kde.kdeconnect.device", "supportedPlugins")
+        .await
+        .with_context(|| format!("unable to read supported plugins for device {device}"))?;
+    if parse_dbus_string_array(&raw).iter().any(|p| p == plugin) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "device {device} does not support the {plugin} plugin; enable it in the KDE Connect app"
+    ))
+}
+
 async fn read_dbus_int_prop(
     device: &str,
     plugin: &str,
@@ -700,113 +1135,6 @@ async fn run_busctl_get_property(path: &str, interface: &str, prop: &str) -> Res
     Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
 }
 
-async fn handle_media_action(
-    shared: &Shared,
-    device: Option<String>,
-    action: MediaAction,
-) -> Result<String> {
-    let dev = resolve_device(shared, device).await?;
-    let path = format!("/modules/kdeconnect/devices/{dev}/mprisremote");
-    let iface = "org.kde.kdeconnect.device.mprisremote";
-
-    match action {
-        MediaAction::Status => media_status(&path, iface).await,
-        MediaAction::PlayPause => {
-            media_send_action(&path, "PlayPause").await?;
-            Ok(format!("Sent PlayPause to {dev}"))
-        }
-        MediaAction::Next => {
-            media_send_action(&path, "Next").await?;
-            Ok(format!("Sent Next to {dev}"))
-        }
-        MediaAction::Previous => {
-            media_send_action(&path, "Previous").await?;
-            Ok(format!("Sent Previous to {dev}"))
-        }
-        MediaAction::Stop => {
-            media_send_action(&path, "Stop").await?;
-            Ok(format!("Sent Stop to {dev}"))
-        }
-        MediaAction::Seek { ms } => {
-            media_seek(&path, ms).await?;
-            Ok(format!("Seeked {dev} by {ms}ms"))
-        }
-        MediaAction::VolumeSet { value } => {
-            media_set_volume(&path, value).await?;
-            Ok(format!("Set phone media volume to {value}% on {dev}"))
-        }
-        MediaAction::PlayerList => media_player_list(&path, iface).await,
-        MediaAction::PlayerSet { name } => {
-            media_set_player(&path, &name).await?;
-            Ok(format!("Set active phone player to '{name}'"))
-        }
-    }
-}
-
-async fn media_status(path: &str, iface: &str) -> Result<String> {
-    let player = parse_dbus_string(&run_busctl_get_property(path, iface, "player").await?)
-        .unwrap_or_else(|| "Unknown".to_string());
-    let title = parse_dbus_string(&run_busctl_get_property(path, iface, "title").await?)
-        .unwrap_or_else(|| "--".to_string());
-    let artist = parse_dbus_string(&run_busctl_get_property(path, iface, "artist").await?)
-        .unwrap_or_else(|| "--".to_string());
-    let is_playing = parse_dbus_bool(&run_busctl_get_property(path, iface, "isPlaying").await?)
-        .unwrap_or(false);
-    let volume = parse_dbus_int(&run_busctl_get_property(path, iface, "volume").await?)
-        .unwrap_or(0);
-
-    Ok(format!(
-        "Player: {player}\nState: {}\nTitle: {title}\nArtist: {artist}\nVolume: {volume}%",
-        if is_playing { "Playing" } else { "Paused" }
-    ))
-}
-
-async fn media_player_list(path: &str, iface: &str) -> Result<String> {
-    let raw = run_busctl_get_property(path, iface, "playerList").await?;
-    let players = parse_dbus_string_array(&raw);
-    if players.is_empty() {
-        return Ok("No phone media players reported".to_string());
-    }
-    Ok(format!("Players:\n{}", players.join("\n")))
-}
-
-async fn media_send_action(path: &str, action: &str) -> Result<()> {
-    run_busctl_call(path, "org.kde.kdeconnect.device.mprisremote", "sendAction", &["s", action])
-        .await
-}
-
-async fn media_seek(path: &str, ms: i32) -> Result<()> {
-    run_busctl_call(
-        path,
-        "org.kde.kdeconnect.device.mprisremote",
-        "seek",
-        &["i", &ms.to_string()],
-    )
-    .await
-}
-
-async fn media_set_volume(path: &str, value: u8) -> Result<()> {
-    run_busctl_set_property(
-        path,
-        "org.kde.kdeconnect.device.mprisremote",
-        "volume",
-        "i",
-        &value.to_string(),
-    )
-    .await
-}
-
-async fn media_set_player(path: &str, name: &str) -> Result<()> {
-    run_busctl_set_property(
-        path,
-        "org.kde.kdeconnect.device.mprisremote",
-        "player",
-        "s",
-        name,
-    )
-    .await
-}
-
 async fn run_busctl_call(path: &str, interface: &str, member: &str, tail_args: &[&str]) -> Result<()> {
     if !command_exists("busctl").await {
         return Err(anyhow!("busctl not found"));
@@ -894,14 +1222,6 @@ fn parse_dbus_string(raw: &str) -> Option<String> {
     }
 }
 
-fn parse_dbus_bool(raw: &str) -> Option<bool> {
-    raw.split_whitespace().nth(1)?.parse::<bool>().ok()
-}
-
-fn parse_dbus_int(raw: &str) -> Option<i32> {
-    raw.split_whitespace().nth(1)?.parse::<i32>().ok()
-}
-
 fn parse_dbus_string_array(raw: &str) -> Vec<String> {
     let re = Regex::new("\"([^\"]+)\"").unwrap();
     re.captures_iter(raw)
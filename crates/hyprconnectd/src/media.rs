@@ -0,0 +1,717 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use hyprconnect_core::rules::HYPRCONNECT_APP;
+use hyprconnect_core::{
+    state_export_dir, IpcResponse, MediaAction, MediaPlayerEntry, MediaState, MediaUpdate,
+    NotificationUrgency,
+};
+use notify_rust::Notification;
+use tokio::process::Command;
+use tokio::sync::watch;
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::notifications::desktop_urgency;
+use crate::share::percent_decode;
+use crate::{
+    into_data_response, into_response, parse_dbus_string, parse_dbus_string_array, resolve_device, run_busctl_call,
+    run_busctl_get_property, run_busctl_set_property, Shared,
+};
+
+pub const MPRIS_REMOTE_IFACE: &str = "org.kde.kdeconnect.device.mprisremote";
+/// Prefix of the MPRIS players kdeconnectd exports on the session bus for phone players.
+const MPRIS_BRIDGE_PREFIX: &str = "org.mpris.MediaPlayer2.kdeconnect";
+
+/// Per-device media state kept by the daemon.
+#[derive(Default)]
+pub struct MediaTracker {
+    /// Last state read for each device, refreshed on mprisremote signals.
+    states: HashMap<String, MediaState>,
+    /// Bumped whenever a cached state changes; `Watch` requests wait on it.
+    updates: watch::Sender<u64>,
+    /// Album art copied into the runtime dir, keyed by device.
+    art: HashMap<String, CachedArt>,
+    /// Volume to restore on unmute, keyed by device.
+    muted: HashMap<String, u8>,
+    /// Last track seen by the signal handler, keyed by device.
+    tracks: HashMap<String, TrackKey>,
+    /// Desktop id of the track-change notification, replaced on the next change.
+    track_notification: Option<u32>,
+    /// Device and player that most recently started playing, for `auto_target`.
+    last_playing: Option<(String, String)>,
+    /// Devices whose current player is being switched through by `ListAll` or `MediaPauseAll`.
+    scanning: HashSet<String>,
+    /// Bumped when a scan starts or ends so signal reads overlapping one are discarded.
+    scan_epoch: u64,
+}
+
+#[derive(PartialEq)]
+struct TrackKey {
+    player: Option<String>,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+}
+
+impl TrackKey {
+    fn of(state: &MediaState) -> Self {
+        Self {
+            player: state.player.clone(),
+            title: state.title.clone(),
+            artist: state.artist.clone(),
+            album: state.album.clone(),
+        }
+    }
+}
+
+impl MediaTracker {
+    /// Store `state`, waking watchers if it differs from the cached one.
+    fn update(&mut self, device: &str, state: Option<MediaState>) {
+        if let Some(now) = state.as_ref().filter(|s| s.playing) {
+            let was_playing = self
+                .states
+                .get(device)
+                .is_some_and(|prev| prev.playing && prev.player == now.player);
+            if let (false, Some(player)) = (was_playing, &now.player) {
+                self.last_playing = Some((device.to_string(), player.clone()));
+            }
+        }
+        let changed = match state {
+            Some(state) => self.states.insert(device.to_string(), state.clone()) != Some(state),
+            None => self.states.remove(device).is_some(),
+        };
+        if changed {
+            self.updates.send_modify(|g| *g += 1);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CachedArt {
+    source: String,
+    path: PathBuf,
+}
+
+pub async fn handle_action(
+    shared: &Shared,
+    device: Option<String>,
+    auto_target: bool,
+    action: MediaAction,
+) -> IpcResponse {
+    match action {
+        MediaAction::Watch { after } => {
+            return into_data_response(watch(shared, device, after).await);
+        }
+        MediaAction::ListAll => return into_data_response(list_all(shared).await),
+        _ => {}
+    }
+    let target = if auto_target {
+        auto_device(shared).await
+    } else {
+        resolve_device(shared, device).await
+    };
+    let dev = match target {
+        Ok(dev) => dev,
+        Err(err) => return into_response(Err(err)),
+    };
+    let path = mpris_path(&dev);
+
+    let result = match action {
+        MediaAction::Status => {
+            let state = read_state(shared, &dev).await;
+            if let Ok(state) = &state {
+                shared.media.lock().await.update(&dev, Some(state.clone()));
+            }
+            return into_data_response(state);
+        }
+        MediaAction::PlayPause => send_action(&path, "PlayPause")
+            .await
+            .map(|_| format!("Sent PlayPause to {dev}")),
+        MediaAction::Next => send_action(&path, "Next")
+            .await
+            .map(|_| format!("Sent Next to {dev}")),
+        MediaAction::Previous => send_action(&path, "Previous")
+            .await
+            .map(|_| format!("Sent Previous to {dev}")),
+        MediaAction::Stop => send_action(&path, "Stop")
+            .await
+            .map(|_| format!("Sent Stop to {dev}")),
+        MediaAction::Seek { ms } => seek(&path, ms)
+            .await
+            .map(|_| format!("Seeked {dev} by {ms}ms")),
+        MediaAction::SeekTo { position_ms } => seek_to(&path, position_ms)
+            .await
+            .map(|_| format!("Seeked {dev} to {position_ms}ms")),
+        MediaAction::VolumeSet { value } => set_volume(&path, value)
+            .await
+            .map(|_| format!("Set phone media volume to {value}% on {dev}")),
+        MediaAction::VolumeStep { delta } => step_volume(&path, delta)
+            .await
+            .map(|value| format!("Set phone media volume to {value}% on {dev}")),
+        MediaAction::MuteToggle => toggle_mute(shared, &dev, &path).await,
+        MediaAction::PlayerList => player_list(&path).await,
+        MediaAction::PlayerSet { name } => set_player(&path, &name)
+            .await
+            .map(|_| format!("Set active phone player to '{name}'")),
+        MediaAction::Watch { .. } | MediaAction::ListAll => {
+            unreachable!("handled before device resolution")
+        }
+    };
+    into_response(result)
+}
+
+/// Re-read `device`'s state after an mprisremote signal and announce track changes.
+pub async fn handle_signal(shared: &Shared, device: &str) {
+    let epoch = {
+        let tracker = shared.media.lock().await;
+        if tracker.scanning.contains(device) {
+            return;
+        }
+        tracker.scan_epoch
+    };
+    let state = read_state(shared, device).await.ok();
+    let new_track = {
+        let mut tracker = shared.media.lock().await;
+        if tracker.scan_epoch != epoch {
+            // A scan switched players under this read; its end triggers a fresh one.
+            return;
+        }
+        tracker.update(device, state.clone());
+        match state.as_ref().filter(|s| s.title.is_some()) {
+            Some(state) => {
+                let key = TrackKey::of(state);
+                let changed = tracker.tracks.get(device) != Some(&key);
+                tracker.tracks.insert(device.to_string(), key);
+                changed
+            }
+            None => false,
+        }
+    };
+    if let Some(state) = state.filter(|_| new_track) {
+        notify_track(shared, &state).await;
+    }
+}
+
+/// Low-urgency "now playing" notification that replaces the previous one.
+async fn notify_track(shared: &Shared, state: &MediaState) {
+    let config = &shared.config;
+    if !config.notifications_enabled || !config.media_notifications_enabled {
+        return;
+    }
+    let player_allowed = config.media_notification_players.is_empty()
+        || state.player.as_ref().is_some_and(|player| {
+            config
+                .media_notification_players
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(player))
+        });
+    if !player_allowed || hyprland_fullscreen().await {
+        return;
+    }
+
+    let summary = state.title.as_deref().unwrap_or_default();
+    let body = [&state.artist, &state.album]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let decision = config.notification_rules.evaluate(
+        HYPRCONNECT_APP,
+        summary,
+        &body,
+        NotificationUrgency::Low,
+        Local::now(),
+    );
+    if !decision.show {
+        return;
+    }
+
+    let previous = shared.media.lock().await.track_notification;
+    let mut notification = Notification::new();
+    notification
+        .appname(HYPRCONNECT_APP)
+        .summary(summary)
+        .body(&body)
+        .urgency(desktop_urgency(decision.urgency));
+    match &state.album_art_path {
+        Some(path) => notification.image_path(path),
+        None => notification.icon("audio-x-generic"),
+    };
+    if let Some(previous) = previous {
+        notification.id(previous);
+    }
+    // `show` blocks on the notification server's reply; keep it off the runtime and the lock.
+    let shown = tokio::task::spawn_blocking(move || notification.show().map(|h| h.id())).await;
+    if let Ok(Ok(id)) = shown {
+        shared.media.lock().await.track_notification = Some(id);
+    }
+}
+
+/// Whether Hyprland's focused window is fullscreen; false when `hyprctl` is unavailable.
+async fn hyprland_fullscreen() -> bool {
+    let Ok(output) = Command::new("hyprctl")
+        .args(["activewindow", "-j"])
+        .stderr(Stdio::null())
+        .output()
+        .await
+    else {
+        return false;
+    };
+    if !output.status.success() {
+        return false;
+    }
+    let Ok(window) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+        return false;
+    };
+    // Older releases report a bool, newer ones a fullscreen mode where 0 means windowed.
+    match window.get("fullscreen") {
+        Some(serde_json::Value::Bool(fullscreen)) => *fullscreen,
+        Some(serde_json::Value::Number(mode)) => mode.as_u64().is_some_and(|m| m > 0),
+        _ => false,
+    }
+}
+
+/// Drop cached state for devices that connected or went away, so watchers re-resolve.
+pub async fn reachability_changed(shared: &Shared, devices: &[String]) {
+    let mut tracker = shared.media.lock().await;
+    for device in devices {
+        tracker.states.remove(device);
+    }
+    if !devices.is_empty() {
+        tracker.updates.send_modify(|g| *g += 1);
+    }
+}
+
+/// The device that most recently started playing, made to use that player.
+async fn auto_device(shared: &Shared) -> Result<String> {
+    let last = shared.media.lock().await.last_playing.clone();
+    let Some((device, player)) = last else {
+        return resolve_device(shared, None).await;
+    };
+    let reachable = {
+        let state = shared.state.read().await;
+        state.devices.iter().any(|d| d.id == device && d.reachable)
+    };
+    if !reachable {
+        return resolve_device(shared, None).await;
+    }
+    let path = mpris_path(&device);
+    let current = run_busctl_get_property(&path, MPRIS_REMOTE_IFACE, "player").await?;
+    if parse_dbus_string(&current).as_deref() != Some(player.as_str()) {
+        set_player(&path, &player).await?;
+    }
+    Ok(device)
+}
+
+/// Every player on every reachable device, read by briefly making each one current.
+async fn list_all(shared: &Shared) -> Result<Vec<MediaPlayerEntry>> {
+    let devices = reachable_devices(shared).await;
+    if devices.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conn = zbus::Connection::session().await?;
+    let mut entries = Vec::new();
+    for (device, device_name) in devices {
+        let path = mpris_path(&device);
+        let Ok(players) = device_players(&path).await else {
+            continue;
+        };
+        let Some(active) = begin_scan(shared, &device, &path).await else {
+            continue;
+        };
+        for player in &players {
+            let read = async {
+                set_player(&path, player).await?;
+                let props = read_props(&conn, &device).await?;
+                anyhow::Ok(state_from_props(&device, &props))
+            }
+            .await;
+            if let Ok(state) = read {
+                entries.push(MediaPlayerEntry {
+                    device_name: device_name.clone(),
+                    active: *player == active,
+                    state,
+                });
+            }
+        }
+        end_scan(shared, &device, &path, &active).await;
+    }
+    Ok(entries)
+}
+
+/// Send `Pause` to every player on every reachable device.
+pub async fn pause_all(shared: &Shared) -> Result<String> {
+    let mut paused = 0;
+    for (device, _) in reachable_devices(shared).await {
+        let path = mpris_path(&device);
+        let Ok(players) = device_players(&path).await else {
+            continue;
+        };
+        let Some(active) = begin_scan(shared, &device, &path).await else {
+            continue;
+        };
+        for player in &players {
+            if set_player(&path, player).await.is_ok() && send_action(&path, "Pause").await.is_ok() {
+                paused += 1;
+            }
+        }
+        end_scan(shared, &device, &path, &active).await;
+    }
+    if paused == 0 {
+        return Err(anyhow!("no phone media players to pause"));
+    }
+    Ok(format!("Paused {paused} player(s)"))
+}
+
+async fn reachable_devices(shared: &Shared) -> Vec<(String, String)> {
+    let state = shared.state.read().await;
+    state
+        .devices
+        .iter()
+        .filter(|d| d.reachable && d.paired)
+        .map(|d| (d.id.clone(), d.name.clone()))
+        .collect()
+}
+
+async fn device_players(path: &str) -> Result<Vec<String>> {
+    let raw = run_busctl_get_property(path, MPRIS_REMOTE_IFACE, "playerList").await?;
+    Ok(parse_dbus_string_array(&raw))
+}
+
+/// Mark `device` as scanning and return its current player, or `None` if a scan is running.
+async fn begin_scan(shared: &Shared, device: &str, path: &str) -> Option<String> {
+    {
+        let mut tracker = shared.media.lock().await;
+        if !tracker.scanning.insert(device.to_string()) {
+            return None;
+        }
+        tracker.scan_epoch += 1;
+    }
+    let raw = run_busctl_get_property(path, MPRIS_REMOTE_IFACE, "player").await;
+    let active = raw.ok().and_then(|raw| parse_dbus_string(&raw));
+    if active.is_none() {
+        let mut tracker = shared.media.lock().await;
+        tracker.scanning.remove(device);
+        tracker.scan_epoch += 1;
+    }
+    active
+}
+
+/// Restore the player that was current before the scan and resync the cached state.
+async fn end_scan(shared: &Shared, device: &str, path: &str, active: &str) {
+    if let Err(err) = set_player(path, active).await {
+        eprintln!("failed to restore phone player on {device}: {err:#}");
+    }
+    {
+        let mut tracker = shared.media.lock().await;
+        tracker.scanning.remove(device);
+        tracker.scan_epoch += 1;
+    }
+    handle_signal(shared, device).await;
+}
+
+/// Long-poll for media changes: returns at once unless `after` is the current generation.
+async fn watch(shared: &Shared, device: Option<String>, after: Option<u64>) -> Result<MediaUpdate> {
+    let mut updates = shared.media.lock().await.updates.subscribe();
+    if let Some(after) = after {
+        updates.wait_for(|g| *g != after).await?;
+    }
+    let generation = *updates.borrow();
+
+    let Ok(device) = resolve_device(shared, device).await else {
+        return Ok(MediaUpdate {
+            generation,
+            state: None,
+        });
+    };
+    let cached = shared.media.lock().await.states.get(&device).cloned();
+    let state = match cached {
+        Some(state) => Some(state),
+        None => {
+            let state = read_state(shared, &device).await.ok();
+            // Cache without bumping the generation; other watchers have nothing new to see.
+            if let Some(state) = &state {
+                shared
+                    .media
+                    .lock()
+                    .await
+                    .states
+                    .insert(device.clone(), state.clone());
+            }
+            state
+        }
+    };
+    Ok(MediaUpdate { generation, state })
+}
+
+/// Read the active player's state in one `GetAll` and refresh the album art copy.
+pub async fn read_state(shared: &Shared, device: &str) -> Result<MediaState> {
+    let conn = zbus::Connection::session().await?;
+    let props = read_props(&conn, device).await?;
+    let mut state = state_from_props(device, &props);
+    let player = state.player.clone();
+    // The plugin property, where present, may hold the phone-side URL; the MPRIS bridge's
+    // metadata points at the copy in kdeconnectd's album art cache.
+    let plugin_art = string_prop(&props, "albumArtUrl");
+    let bridge_art = match plugin_art.as_deref() {
+        Some(url) if local_path(url).is_some() => None,
+        _ => bridge_art_url(&conn, shared, device, player.as_deref()).await,
+    };
+    let local_art = [&plugin_art, &bridge_art]
+        .into_iter()
+        .flatten()
+        .find(|url| local_path(url).is_some())
+        .cloned();
+    let album_art_path = match &local_art {
+        Some(url) => cache_album_art(shared, device, url).await,
+        None => None,
+    };
+    state.album_art_url = plugin_art.or(bridge_art);
+    state.album_art_path = album_art_path.map(|p| p.to_string_lossy().to_string());
+    Ok(state)
+}
+
+async fn read_props(conn: &zbus::Connection, device: &str) -> Result<HashMap<String, OwnedValue>> {
+    let proxy = PropertiesProxy::builder(conn)
+        .destination("org.kde.kdeconnect")?
+        .path(mpris_path(device))?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    proxy
+        .get_all(InterfaceName::from_static_str_unchecked(MPRIS_REMOTE_IFACE))
+        .await
+        .with_context(|| format!("mprisremote plugin is not available on {device}"))
+}
+
+/// State of the current player without album art, which only `read_state` resolves.
+fn state_from_props(device: &str, props: &HashMap<String, OwnedValue>) -> MediaState {
+    MediaState {
+        device: device.to_string(),
+        player: string_prop(props, "player"),
+        title: string_prop(props, "title"),
+        artist: string_prop(props, "artist"),
+        album: string_prop(props, "album"),
+        playing: bool_prop(props, "isPlaying").unwrap_or(false),
+        volume: int_prop(props, "volume").map(|v| v.clamp(0, 100) as u8),
+        position_ms: int_prop(props, "position").and_then(|v| u64::try_from(v).ok()),
+        length_ms: int_prop(props, "length")
+            .and_then(|v| u64::try_from(v).ok())
+            .filter(|v| *v > 0),
+        can_seek: bool_prop(props, "canSeek").unwrap_or(false),
+        album_art_url: None,
+        album_art_path: None,
+    }
+}
+
+/// Art URL from the bridged MPRIS player whose identity names `device` (and `player`).
+async fn bridge_art_url(
+    conn: &zbus::Connection,
+    shared: &Shared,
+    device: &str,
+    player: Option<&str>,
+) -> Option<String> {
+    let device_name = {
+        let state = shared.state.read().await;
+        state.devices.iter().find(|d| d.id == device)?.name.clone()
+    };
+    let dbus = zbus::fdo::DBusProxy::new(conn).await.ok()?;
+    let names = dbus.list_names().await.ok()?;
+
+    let mut fallback = None;
+    for name in names.iter().filter(|n| n.starts_with(MPRIS_BRIDGE_PREFIX)) {
+        let Ok(proxy) = bridge_properties(conn, name.as_str()).await else {
+            continue;
+        };
+        let Ok(identity) = proxy
+            .get(
+                InterfaceName::from_static_str_unchecked("org.mpris.MediaPlayer2"),
+                "Identity",
+            )
+            .await
+        else {
+            continue;
+        };
+        let identity = String::try_from(identity).unwrap_or_default();
+        if !identity.contains(&device_name) {
+            continue;
+        }
+        let Ok(metadata) = proxy
+            .get(
+                InterfaceName::from_static_str_unchecked("org.mpris.MediaPlayer2.Player"),
+                "Metadata",
+            )
+            .await
+        else {
+            continue;
+        };
+        let metadata = HashMap::<String, OwnedValue>::try_from(metadata).unwrap_or_default();
+        let Some(url) = string_prop(&metadata, "mpris:artUrl") else {
+            continue;
+        };
+        if player.is_some_and(|p| identity.contains(p)) {
+            return Some(url);
+        }
+        fallback.get_or_insert(url);
+    }
+    fallback
+}
+
+async fn bridge_properties(
+    conn: &zbus::Connection,
+    name: &str,
+) -> Result<PropertiesProxy<'static>> {
+    let proxy = PropertiesProxy::builder(conn)
+        .destination(name.to_string())?
+        .path("/org/mpris/MediaPlayer2")?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
+
+/// Copy local album art to `${XDG_RUNTIME_DIR}/hyprconnect/media/`, once per source URL.
+async fn cache_album_art(shared: &Shared, device: &str, url: &str) -> Option<PathBuf> {
+    let cached = shared.media.lock().await.art.get(device).cloned();
+    if let Some(cached) = cached {
+        if cached.source == url && cached.path.exists() {
+            return Some(cached.path);
+        }
+    }
+
+    let source = local_path(url)?;
+    let dir = state_export_dir().join("media");
+    tokio::fs::create_dir_all(&dir).await.ok()?;
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let extension = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "img".to_string());
+    let target = dir.join(format!("{device}-{:016x}.{extension}", hasher.finish()));
+    tokio::fs::copy(&source, &target).await.ok()?;
+
+    let previous = shared.media.lock().await.art.insert(
+        device.to_string(),
+        CachedArt {
+            source: url.to_string(),
+            path: target.clone(),
+        },
+    );
+    if let Some(previous) = previous.filter(|p| p.path != target) {
+        let _ = tokio::fs::remove_file(&previous.path).await;
+    }
+    Some(target)
+}
+
+fn local_path(url: &str) -> Option<PathBuf> {
+    match url.strip_prefix("file://") {
+        Some(path) => Some(PathBuf::from(percent_decode(path))),
+        None if url.starts_with('/') => Some(PathBuf::from(url)),
+        None => None,
+    }
+}
+
+fn mpris_path(device: &str) -> String {
+    format!("/modules/kdeconnect/devices/{device}/mprisremote")
+}
+
+async fn player_list(path: &str) -> Result<String> {
+    let raw = run_busctl_get_property(path, MPRIS_REMOTE_IFACE, "playerList").await?;
+    let players = parse_dbus_string_array(&raw);
+    if players.is_empty() {
+        return Ok("No phone media players reported".to_string());
+    }
+    Ok(format!("Players:\n{}", players.join("\n")))
+}
+
+async fn send_action(path: &str, action: &str) -> Result<()> {
+    run_busctl_call(path, MPRIS_REMOTE_IFACE, "sendAction", &["s", action]).await
+}
+
+async fn seek(path: &str, ms: i32) -> Result<()> {
+    run_busctl_call(path, MPRIS_REMOTE_IFACE, "seek", &["i", &ms.to_string()]).await
+}
+
+async fn seek_to(path: &str, position_ms: u64) -> Result<()> {
+    let position = i32::try_from(position_ms).context("seek position is out of range")?;
+    run_busctl_set_property(path, MPRIS_REMOTE_IFACE, "position", "i", &position.to_string())
+        .await
+}
+
+async fn volume(path: &str) -> Result<u8> {
+    let raw = run_busctl_get_property(path, MPRIS_REMOTE_IFACE, "volume").await?;
+    // busctl prints the property as `i <value>`.
+    raw.split_whitespace()
+        .nth(1)
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(|v| v.clamp(0, 100) as u8)
+        .ok_or_else(|| anyhow!("phone did not report a media volume"))
+}
+
+async fn step_volume(path: &str, delta: i32) -> Result<u8> {
+    let current = volume(path).await?;
+    let value = (i32::from(current) + delta).clamp(0, 100) as u8;
+    set_volume(path, value).await?;
+    Ok(value)
+}
+
+/// Mute by setting the volume to 0, remembering the previous level for the next toggle.
+async fn toggle_mute(shared: &Shared, device: &str, path: &str) -> Result<String> {
+    let current = volume(path).await?;
+    // A volume changed elsewhere since muting means the device is no longer muted.
+    let muted = shared.media.lock().await.muted.remove(device);
+    match muted.filter(|_| current == 0) {
+        Some(previous) => {
+            set_volume(path, previous).await?;
+            Ok(format!("Unmuted phone media on {device} ({previous}%)"))
+        }
+        None if current == 0 => Err(anyhow!("phone media on {device} is already at 0%")),
+        None => {
+            set_volume(path, 0).await?;
+            shared.media.lock().await.muted.insert(device.to_string(), current);
+            Ok(format!("Muted phone media on {device}"))
+        }
+    }
+}
+
+async fn set_volume(path: &str, value: u8) -> Result<()> {
+    run_busctl_set_property(path, MPRIS_REMOTE_IFACE, "volume", "i", &value.to_string()).await
+}
+
+async fn set_player(path: &str, name: &str) -> Result<()> {
+    run_busctl_set_property(path, MPRIS_REMOTE_IFACE, "player", "s", name).await
+}
+
+fn string_prop(props: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    match props.get(key).map(|v| &**v) {
+        Some(Value::Str(s)) if !s.is_empty() => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn bool_prop(props: &HashMap<String, OwnedValue>, key: &str) -> Option<bool> {
+    match props.get(key).map(|v| &**v) {
+        Some(Value::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+fn int_prop(props: &HashMap<String, OwnedValue>, key: &str) -> Option<i64> {
+    match props.get(key).map(|v| &**v) {
+        Some(Value::I32(n)) => Some(i64::from(*n)),
+        Some(Value::I64(n)) => Some(*n),
+        Some(Value::U32(n)) => Some(i64::from(*n)),
+        Some(Value::U64(n)) => i64::try_from(*n).ok(),
+        _ => None,
+    }
+}
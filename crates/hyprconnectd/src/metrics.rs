@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use hyprconnect_core::DaemonState;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+
+use crate::export::write_atomic;
+
+/// Process-lifetime counters exposed alongside the per-device gauges.
+#[derive(Default)]
+pub struct Metrics {
+    ipc_requests: Mutex<BTreeMap<String, u64>>,
+    refresh_failures: AtomicU64,
+    signal_refreshes: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_ipc_request(&self, kind: &str) {
+        let mut map = self.ipc_requests.lock().unwrap();
+        *map.entry(kind.to_string()).or_default() += 1;
+    }
+
+    pub fn record_refresh_failure(&self) {
+        self.refresh_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_signal_refresh(&self) {
+        self.signal_refreshes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render node_exporter textfile / OpenMetrics-compatible exposition text.
+    pub fn render(&self, state: &DaemonState) -> String {
+        let mut out = String::new();
+
+        gauge_header(&mut out, "battery_percent", "Phone battery charge in percent.");
+        for d in &state.devices {
+            if let Some(v) = d.battery_percent {
+                sample(&mut out, "battery_percent", &d.id, &d.name, u64::from(v));
+            }
+        }
+
+        gauge_header(&mut out, "charging", "1 when the phone reports it is charging.");
+        for d in &state.devices {
+            if let Some(v) = d.charging {
+                sample(&mut out, "charging", &d.id, &d.name, u64::from(v));
+            }
+        }
+
+        gauge_header(&mut out, "signal_percent", "Cellular signal strength in percent.");
+        for d in &state.devices {
+            if let Some(v) = d.signal_percent {
+                sample(&mut out, "signal_percent", &d.id, &d.name, u64::from(v));
+            }
+        }
+
+        gauge_header(&mut out, "reachable", "1 when the device is reachable over KDE Connect.");
+        for d in &state.devices {
+            sample(&mut out, "reachable", &d.id, &d.name, u64::from(d.reachable));
+        }
+
+        gauge_header(&mut out, "mounted", "1 when the device filesystem is mounted.");
+        for d in &state.devices {
+            sample(&mut out, "mounted", &d.id, &d.name, u64::from(d.mounted));
+        }
+
+        let _ = writeln!(out, "# HELP hyprconnect_ipc_requests_total IPC requests handled by hyprconnectd.");
+        let _ = writeln!(out, "# TYPE hyprconnect_ipc_requests_total counter");
+        for (kind, count) in self.ipc_requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "hyprconnect_ipc_requests_total{{type=\"{}\"}} {count}",
+                escape_label(kind)
+            );
+        }
+
+        let _ = writeln!(out, "# HELP hyprconnect_refresh_failures_total Failed device state refreshes.");
+        let _ = writeln!(out, "# TYPE hyprconnect_refresh_failures_total counter");
+        let _ = writeln!(
+            out,
+            "hyprconnect_refresh_failures_total {}",
+            self.refresh_failures.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(out, "# HELP hyprconnect_signal_refreshes_total Refreshes triggered by KDE Connect D-Bus signals.");
+        let _ = writeln!(out, "# TYPE hyprconnect_signal_refreshes_total counter");
+        let _ = writeln!(
+            out,
+            "hyprconnect_signal_refreshes_total {}",
+            self.signal_refreshes.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+fn gauge_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP hyprconnect_{name} {help}");
+    let _ = writeln!(out, "# TYPE hyprconnect_{name} gauge");
+}
+
+fn sample(out: &mut String, name: &str, id: &str, device_name: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "hyprconnect_{name}{{device_id=\"{}\",device_name=\"{}\"}} {value}",
+        escape_label(id),
+        escape_label(device_name)
+    );
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub async fn write_textfile(path: &Path, metrics: &Metrics, state: &DaemonState) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create metrics dir: {}", parent.display()))?;
+    }
+    write_atomic(path, metrics.render(state).as_bytes()).await
+}
+
+/// Serve `GET /metrics` on a loopback-only TCP listener.
+pub async fn serve(
+    listen: &str,
+    metrics: Arc<Metrics>,
+    state: Arc<RwLock<DaemonState>>,
+) -> Result<()> {
+    let addr: SocketAddr = listen
+        .parse()
+        .with_context(|| format!("invalid metrics_listen address: {listen}"))?;
+    if !addr.ip().is_loopback() {
+        return Err(anyhow!(
+            "metrics_listen must be a loopback address, got {addr}"
+        ));
+    }
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind metrics listener: {addr}"))?;
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let Ok(n) = stream.read(&mut buf).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+            let response = if path == "/metrics" {
+                let body = metrics.render(&*state.read().await);
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
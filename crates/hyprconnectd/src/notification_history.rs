@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hyprconnect_core::{state_dir, NotificationRecord, PhoneNotification};
+use regex::RegexBuilder;
+
+use crate::export::write_atomic;
+
+/// Bounded per-device notification history, cached in memory and persisted as
+/// `$XDG_STATE_HOME/hyprconnect/notifications/<device>.json`.
+pub struct NotificationHistory {
+    limit: usize,
+    cache: HashMap<String, Vec<NotificationRecord>>,
+}
+
+pub struct HistoryQuery {
+    pub device: Option<String>,
+    pub app: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub grep: Option<String>,
+}
+
+impl NotificationHistory {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Record a posted or updated notification. An update to a notification that is
+    /// still active replaces its entry instead of appending a new one.
+    pub async fn record_posted(&mut self, notification: &PhoneNotification) -> Result<()> {
+        let limit = self.limit;
+        let records = self.load(&notification.device).await?;
+        let existing = records
+            .iter_mut()
+            .rev()
+            .find(|r| r.id == notification.id && !r.dismissed);
+
+        match existing {
+            Some(record) => {
+                record.app_name = notification.app_name.clone();
+                record.title = notification.title.clone();
+                record.text = notification.text.clone();
+            }
+            None => records.push(NotificationRecord {
+                device: notification.device.clone(),
+                id: notification.id.clone(),
+                app_name: notification.app_name.clone(),
+                title: notification.title.clone(),
+                text: notification.text.clone(),
+                posted_at: Utc::now(),
+                dismissed: false,
+            }),
+        }
+
+        if records.len() > limit {
+            let excess = records.len() - limit;
+            records.drain(..excess);
+        }
+        self.persist(&notification.device).await
+    }
+
+    /// Mark active entries as dismissed; `id = None` marks every entry for the device.
+    pub async fn record_dismissed(&mut self, device: &str, id: Option<&str>) -> Result<()> {
+        let records = self.load(device).await?;
+        let mut changed = false;
+        for record in records.iter_mut().filter(|r| !r.dismissed) {
+            if id.is_none_or(|id| record.id == id) {
+                record.dismissed = true;
+                changed = true;
+            }
+        }
+        if changed {
+            self.persist(device).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn query(&mut self, query: &HistoryQuery) -> Result<Vec<NotificationRecord>> {
+        let grep = query
+            .grep
+            .as_deref()
+            .map(|g| RegexBuilder::new(g).case_insensitive(true).build())
+            .transpose()
+            .context("invalid --grep pattern")?;
+
+        let devices = match &query.device {
+            Some(device) => vec![device.clone()],
+            None => self.known_devices().await?,
+        };
+
+        let mut out = Vec::new();
+        for device in devices {
+            for record in self.load(&device).await?.iter() {
+                if let Some(app) = &query.app {
+                    if !record.app_name.eq_ignore_ascii_case(app) {
+                        continue;
+                    }
+                }
+                if let Some(since) = query.since {
+                    if record.posted_at < since {
+                        continue;
+                    }
+                }
+                if let Some(re) = &grep {
+                    if !re.is_match(&record.title) && !re.is_match(&record.text) {
+                        continue;
+                    }
+                }
+                out.push(record.clone());
+            }
+        }
+        out.sort_by_key(|r| r.posted_at);
+        Ok(out)
+    }
+
+    async fn known_devices(&self) -> Result<Vec<String>> {
+        let mut devices: Vec<String> = self.cache.keys().cloned().collect();
+        let dir = history_dir()?;
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            return Ok(devices);
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    if !devices.iter().any(|d| d == stem) {
+                        devices.push(stem.to_string());
+                    }
+                }
+            }
+        }
+        Ok(devices)
+    }
+
+    async fn load(&mut self, device: &str) -> Result<&mut Vec<NotificationRecord>> {
+        if !self.cache.contains_key(device) {
+            let path = history_path(device)?;
+            let records = match tokio::fs::read(&path).await {
+                Ok(raw) => serde_json::from_slice(&raw).unwrap_or_else(|err| {
+                    eprintln!("ignoring corrupt notification history {}: {err}", path.display());
+                    Vec::new()
+                }),
+                Err(_) => Vec::new(),
+            };
+            self.cache.insert(device.to_string(), records);
+        }
+        Ok(self.cache.get_mut(device).expect("history entry was just inserted"))
+    }
+
+    async fn persist(&self, device: &str) -> Result<()> {
+        let Some(records) = self.cache.get(device) else {
+            return Ok(());
+        };
+        let dir = history_dir()?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("failed to create history dir: {}", dir.display()))?;
+        let body = serde_json::to_vec(records)?;
+        write_atomic(&history_path(device)?, &body).await
+    }
+}
+
+fn history_dir() -> Result<PathBuf> {
+    Ok(state_dir()?.join("notifications"))
+}
+
+fn history_path(device: &str) -> Result<PathBuf> {
+    Ok(history_dir()?.join(format!("{device}.json")))
+}
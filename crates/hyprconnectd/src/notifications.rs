@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use chrono::Local;
+use hyprconnect_core::{
+    IpcResponse, NotificationAction, NotificationUrgency, PhoneNotification,
+};
+use notify_rust::{Notification, Urgency};
+use tokio::process::Command;
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+
+use crate::notification_history::HistoryQuery;
+use crate::{calls, incoming, sms};
+use crate::{into_data_response, into_response, resolve_device, Shared};
+
+pub const NOTIFICATIONS_IFACE: &str = "org.kde.kdeconnect.device.notifications";
+const NOTIFICATION_IFACE: &str = "org.kde.kdeconnect.device.notifications.notification";
+
+/// Phone notifications currently mirrored to the desktop, keyed by `device/public_id`,
+/// plus what each desktop notification's actions should act on.
+#[derive(Default)]
+pub struct Mirror {
+    notifications: HashMap<String, PhoneNotification>,
+    desktop_ids: HashMap<String, u32>,
+    action_targets: HashMap<u32, ActionTarget>,
+}
+
+/// Object a desktop notification action ("reply", "dismiss", ...) is routed to.
+#[derive(Debug, Clone)]
+pub enum ActionTarget {
+    PhoneNotification { device: String, id: String },
+    SmsThread { device: String, thread_id: i64 },
+    Call { device: String },
+    ReceivedFile { path: PathBuf },
+    ReceivedUrl { url: String },
+}
+
+impl Mirror {
+    pub fn register_actions(&mut self, desktop_id: u32, target: ActionTarget) {
+        self.action_targets.insert(desktop_id, target);
+    }
+}
+
+fn mirror_key(device: &str, id: &str) -> String {
+    format!("{device}/{id}")
+}
+
+pub async fn handle_signal(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    device: &str,
+    member: &str,
+    public_id: Option<String>,
+) -> Result<()> {
+    let mirror_enabled =
+        shared.config.notifications_enabled && shared.config.phone_notifications_enabled;
+    let history_enabled = shared.history.lock().await.enabled();
+    if !mirror_enabled && !history_enabled {
+        return Ok(());
+    }
+
+    match (member, public_id) {
+        ("notificationPosted" | "notificationUpdated", Some(id)) => {
+            let (mut notification, silent) = read_notification(conn, device, &id).await?;
+            resolve_title(shared, &mut notification).await;
+            if history_enabled {
+                if let Err(err) = shared.history.lock().await.record_posted(&notification).await {
+                    eprintln!("notification history write failed: {err:#}");
+                }
+            }
+            if mirror_enabled {
+                post(shared, conn, notification, silent).await?;
+            }
+            Ok(())
+        }
+        ("notificationRemoved", Some(id)) => {
+            if history_enabled {
+                let mut history = shared.history.lock().await;
+                if let Err(err) = history.record_dismissed(device, Some(&id)).await {
+                    eprintln!("notification history write failed: {err:#}");
+                }
+            }
+            remove(shared, conn, device, &id).await;
+            Ok(())
+        }
+        ("allNotificationsRemoved", _) => {
+            if history_enabled {
+                let mut history = shared.history.lock().await;
+                if let Err(err) = history.record_dismissed(device, None).await {
+                    eprintln!("notification history write failed: {err:#}");
+                }
+            }
+            let ids: Vec<String> = shared
+                .mirror
+                .lock()
+                .await
+                .notifications
+                .values()
+                .filter(|n| n.device == device)
+                .map(|n| n.id.clone())
+                .collect();
+            for id in ids {
+                remove(shared, conn, device, &id).await;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn post(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    notification: PhoneNotification,
+    silent: bool,
+) -> Result<()> {
+    let key = mirror_key(&notification.device, &notification.id);
+
+    let replaces = {
+        let mut mirror = shared.mirror.lock().await;
+        mirror.notifications.insert(key.clone(), notification.clone());
+        mirror.desktop_ids.get(&key).copied()
+    };
+
+    // Silent notifications were already on the phone before it connected; track them only.
+    if silent && replaces.is_none() {
+        return Ok(());
+    }
+
+    let decision = shared.config.notification_rules.evaluate(
+        &notification.app_name,
+        &notification.title,
+        &notification.text,
+        NotificationUrgency::Normal,
+        Local::now(),
+    );
+    if !decision.show {
+        let existing = {
+            let mut mirror = shared.mirror.lock().await;
+            let existing = mirror.desktop_ids.remove(&key);
+            if let Some(id) = existing {
+                mirror.action_targets.remove(&id);
+            }
+            existing
+        };
+        if let Some(existing) = existing {
+            close_desktop_notification(conn, existing).await?;
+        }
+        return Ok(());
+    }
+
+    let summary = if notification.title.is_empty() {
+        notification.app_name.as_str()
+    } else {
+        notification.title.as_str()
+    };
+
+    let mut desktop = Notification::new();
+    desktop
+        .appname(&notification.app_name)
+        .summary(summary)
+        .body(&notification.text)
+        .urgency(desktop_urgency(decision.urgency));
+    if let Some(icon) = &notification.icon_path {
+        desktop.icon(icon);
+    }
+    if let Some(existing) = replaces {
+        desktop.id(existing);
+    }
+    if notification.reply_id.is_some() {
+        desktop.action("reply", "Reply");
+        if notification.dismissable {
+            desktop.action("dismiss", "Dismiss");
+        }
+    }
+
+    // `show` blocks on the notification server's reply; keep it off the runtime and the lock.
+    let desktop_id = tokio::task::spawn_blocking(move || desktop.show().map(|h| h.id()))
+        .await?
+        .context("failed to show mirrored phone notification")?;
+
+    let mut mirror = shared.mirror.lock().await;
+    // Removed on the phone while the desktop notification was being shown.
+    if !mirror.notifications.contains_key(&key) {
+        drop(mirror);
+        close_desktop_notification(conn, desktop_id).await?;
+        return Ok(());
+    }
+    if notification.reply_id.is_some() {
+        let target = ActionTarget::PhoneNotification {
+            device: notification.device.clone(),
+            id: notification.id.clone(),
+        };
+        mirror.register_actions(desktop_id, target);
+    }
+    mirror.desktop_ids.insert(key, desktop_id);
+    Ok(())
+}
+
+pub fn desktop_urgency(urgency: NotificationUrgency) -> Urgency {
+    match urgency {
+        NotificationUrgency::Low => Urgency::Low,
+        NotificationUrgency::Normal => Urgency::Normal,
+        NotificationUrgency::Critical => Urgency::Critical,
+    }
+}
+
+async fn remove(shared: &Shared, conn: &zbus::Connection, device: &str, id: &str) {
+    let key = mirror_key(device, id);
+    let desktop_id = {
+        let mut mirror = shared.mirror.lock().await;
+        mirror.notifications.remove(&key);
+        let desktop_id = mirror.desktop_ids.remove(&key);
+        if let Some(desktop_id) = desktop_id {
+            mirror.action_targets.remove(&desktop_id);
+        }
+        desktop_id
+    };
+    if let Some(desktop_id) = desktop_id {
+        if let Err(err) = close_desktop_notification(conn, desktop_id).await {
+            eprintln!("failed to close mirrored notification: {err:#}");
+        }
+    }
+}
+
+pub async fn close_desktop_notification(conn: &zbus::Connection, id: u32) -> Result<()> {
+    conn.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "CloseNotification",
+        &(id,),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn handle_action(
+    shared: &Shared,
+    device: Option<String>,
+    action: NotificationAction,
+) -> IpcResponse {
+    if let NotificationAction::History { app, since, grep } = action {
+        let query = HistoryQuery {
+            device,
+            app,
+            since,
+            grep,
+        };
+        let result = shared.history.lock().await.query(&query).await;
+        return into_data_response(result);
+    }
+
+    let conn = match zbus::Connection::session().await {
+        Ok(conn) => conn,
+        Err(err) => return into_response(Err(err.into())),
+    };
+    let dev = match resolve_device(shared, device).await {
+        Ok(dev) => dev,
+        Err(err) => return into_response(Err(err)),
+    };
+
+    match action {
+        NotificationAction::List => into_data_response(list(shared, &conn, &dev).await),
+        NotificationAction::Dismiss { id } => {
+            let result = dismiss(&conn, &dev, &id)
+                .await
+                .map(|_| format!("Dismissed notification {id} on {dev}"));
+            into_response(result)
+        }
+        NotificationAction::Reply { id, message } => {
+            let result = send_reply(&conn, &dev, &id, &message)
+                .await
+                .map(|_| format!("Replied to notification {id} on {dev}"));
+            into_response(result)
+        }
+        NotificationAction::History { .. } => unreachable!("history is answered above"),
+    }
+}
+
+async fn list(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    device: &str,
+) -> Result<Vec<PhoneNotification>> {
+    let path = format!("/modules/kdeconnect/devices/{device}/notifications");
+    let proxy = build_proxy(conn, path, NOTIFICATIONS_IFACE).await?;
+    let ids: Vec<String> = proxy
+        .call("activeNotifications", &())
+        .await
+        .context("notifications plugin is not available on this device")?;
+
+    let mut out = Vec::new();
+    for id in ids {
+        if let Ok((mut notification, _)) = read_notification(conn, device, &id).await {
+            resolve_title(shared, &mut notification).await;
+            out.push(notification);
+        }
+    }
+    Ok(out)
+}
+
+async fn dismiss(conn: &zbus::Connection, device: &str, id: &str) -> Result<()> {
+    let proxy = notification_proxy(conn, device, id).await?;
+    let dismissable: bool = proxy
+        .get_property("dismissable")
+        .await
+        .map_err(|_| anyhow!("notification '{id}' not found on {device}"))?;
+    if !dismissable {
+        return Err(anyhow!("notification '{id}' cannot be dismissed"));
+    }
+    proxy.call_method("dismiss", &()).await?;
+    Ok(())
+}
+
+async fn send_reply(conn: &zbus::Connection, device: &str, id: &str, message: &str) -> Result<()> {
+    if message.trim().is_empty() {
+        return Err(anyhow!("reply message is empty"));
+    }
+    let proxy = notification_proxy(conn, device, id).await?;
+    let reply_id: String = proxy
+        .get_property("replyId")
+        .await
+        .map_err(|_| anyhow!("notification '{id}' not found on {device}"))?;
+    if reply_id.is_empty() {
+        return Err(anyhow!("notification '{id}' does not support replies"));
+    }
+    proxy.call_method("sendReply", &(message,)).await?;
+    Ok(())
+}
+
+/// Route "Reply"/"Dismiss" clicks on mirrored desktop notifications back to the phone.
+pub async fn listen_for_actions(shared: Shared) -> Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let rule = zbus::MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("org.freedesktop.Notifications")?
+        .member("ActionInvoked")?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &conn, None).await?;
+
+    while let Some(msg) = stream.next().await {
+        let msg = msg?;
+        let Ok((desktop_id, action)) = msg.body().deserialize::<(u32, String)>() else {
+            continue;
+        };
+
+        let target = shared
+            .mirror
+            .lock()
+            .await
+            .action_targets
+            .get(&desktop_id)
+            .cloned();
+        let Some(target) = target else {
+            continue;
+        };
+
+        let s = shared.clone();
+        let c = conn.clone();
+        tokio::spawn(async move {
+            if let Err(err) = run_action(&s, &c, target, &action).await {
+                eprintln!("notification action '{action}' failed: {err:#}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn run_action(
+    shared: &Shared,
+    conn: &zbus::Connection,
+    target: ActionTarget,
+    action: &str,
+) -> Result<()> {
+    match (target, action) {
+        (ActionTarget::PhoneNotification { device, id }, "reply") => {
+            match prompt_reply(&shared.config.reply_prompt_command).await? {
+                Some(text) => send_reply(conn, &device, &id, &text).await,
+                None => Ok(()),
+            }
+        }
+        (ActionTarget::PhoneNotification { device, id }, "dismiss") => {
+            dismiss(conn, &device, &id).await
+        }
+        (ActionTarget::SmsThread { device, thread_id }, "reply") => {
+            match prompt_reply(&shared.config.reply_prompt_command).await? {
+                Some(text) => sms::reply_to_thread(conn, &device, thread_id, &text).await,
+                None => Ok(()),
+            }
+        }
+        (ActionTarget::Call { device }, "mute") => calls::mute_ringer(conn, &device).await,
+        (ActionTarget::ReceivedFile { path }, "open") => incoming::open(&path.to_string_lossy()),
+        (ActionTarget::ReceivedFile { path }, "show") => incoming::show_in_folder(conn, &path).await,
+        (ActionTarget::ReceivedUrl { url }, "open") => incoming::open(&url),
+        _ => Ok(()),
+    }
+}
+
+/// Run the configured dmenu-style prompt and return the entered line, if any.
+async fn prompt_reply(command: &str) -> Result<Option<String>> {
+    let out = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("failed to run reply prompt: {command}"))?;
+
+    let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || text.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(text))
+}
+
+async fn build_proxy(
+    conn: &zbus::Connection,
+    path: String,
+    interface: &'static str,
+) -> Result<zbus::Proxy<'static>> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+        .destination("org.kde.kdeconnect")?
+        .path(path)?
+        .interface(interface)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
+
+async fn notification_proxy(
+    conn: &zbus::Connection,
+    device: &str,
+    id: &str,
+) -> Result<zbus::Proxy<'static>> {
+    let path = format!("/modules/kdeconnect/devices/{device}/notifications/{id}");
+    build_proxy(conn, path, NOTIFICATION_IFACE).await
+}
+
+/// Messaging apps title notifications from unknown senders with the bare number; show the
+/// contact name instead when the synced address book knows it.
+async fn resolve_title(shared: &Shared, notification: &mut PhoneNotification) {
+    let mut book = shared.contacts.lock().await;
+    if let Some(name) = book.lookup(&notification.device, &notification.title).await {
+        notification.title = name;
+    }
+}
+
+/// Read a notification object's properties. Returns the notification and its `silent` flag.
+async fn read_notification(
+    conn: &zbus::Connection,
+    device: &str,
+    id: &str,
+) -> Result<(PhoneNotification, bool)> {
+    let proxy = notification_proxy(conn, device, id).await?;
+
+    let app_name: String = proxy.get_property("appName").await?;
+    let title: String = proxy.get_property("title").await.unwrap_or_default();
+    let text: String = proxy.get_property("text").await.unwrap_or_default();
+    let icon_path: String = proxy.get_property("iconPath").await.unwrap_or_default();
+    let dismissable: bool = proxy.get_property("dismissable").await.unwrap_or(false);
+    let reply_id: String = proxy.get_property("replyId").await.unwrap_or_default();
+    let silent: bool = proxy.get_property("silent").await.unwrap_or(false);
+
+    let notification = PhoneNotification {
+        device: device.to_string(),
+        id: id.to_string(),
+        app_name,
+        title,
+        text,
+        icon_path: Some(icon_path).filter(|p| !p.is_empty()),
+        dismissable,
+        reply_id: Some(reply_id).filter(|r| !r.is_empty()),
+    };
+    Ok((notification, silent))
+}
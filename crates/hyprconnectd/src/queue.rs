@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Utc};
+use hyprconnect_core::{
+    state_dir, IpcRequest, IpcResponse, NotificationUrgency, QueueAction, QueuedAction,
+};
+
+use crate::export::write_atomic;
+use crate::{audit, into_data_response, into_response, notify_local, Shared};
+
+/// Share and ping requests waiting for their device, persisted as
+/// `$XDG_STATE_HOME/hyprconnect/queue.json` and loaded on first use.
+#[derive(Default)]
+pub struct ActionQueue {
+    items: Option<Vec<QueuedAction>>,
+}
+
+impl ActionQueue {
+    async fn load(&mut self) -> Result<&mut Vec<QueuedAction>> {
+        if self.items.is_none() {
+            let path = queue_path()?;
+            let items = match tokio::fs::read(&path).await {
+                Ok(raw) => serde_json::from_slice(&raw).unwrap_or_else(|err| {
+                    eprintln!("ignoring corrupt action queue {}: {err}", path.display());
+                    Vec::new()
+                }),
+                Err(_) => Vec::new(),
+            };
+            self.items = Some(items);
+        }
+        Ok(self.items.as_mut().expect("queue was just loaded"))
+    }
+
+    async fn persist(&self) -> Result<()> {
+        let Some(items) = &self.items else {
+            return Ok(());
+        };
+        let path = queue_path()?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("failed to create state dir: {}", dir.display()))?;
+        }
+        write_atomic(&path, &serde_json::to_vec(items)?).await
+    }
+
+    /// Remove and return the items matching `pred`, persisting the queue if any were taken.
+    async fn take(&mut self, pred: impl Fn(&QueuedAction) -> bool) -> Result<Vec<QueuedAction>> {
+        let items = self.load().await?;
+        let (taken, kept): (Vec<_>, Vec<_>) = items.drain(..).partition(|i| pred(i));
+        *items = kept;
+        if !taken.is_empty() {
+            self.persist().await?;
+        }
+        Ok(taken)
+    }
+}
+
+pub async fn handle_action(shared: &Shared, action: QueueAction) -> IpcResponse {
+    match action {
+        QueueAction::Add { request } => enqueue(shared, *request)
+            .await
+            .unwrap_or_else(|err| into_response(Err(err))),
+        QueueAction::List => {
+            let mut queue = shared.queue.lock().await;
+            into_data_response(queue.load().await.map(|items| items.clone()))
+        }
+        QueueAction::Cancel { id } => {
+            let result = async {
+                let mut queue = shared.queue.lock().await;
+                let taken = queue.take(|i| i.id == id).await?;
+                match taken.first() {
+                    Some(item) => Ok(format!("Cancelled queued {} for {}", item.summary, item.device)),
+                    None => Err(anyhow!("no queued action with id {id}")),
+                }
+            }
+            .await;
+            into_response(result)
+        }
+    }
+}
+
+/// Run `request` right away when its device is reachable, otherwise persist it.
+async fn enqueue(shared: &Shared, request: IpcRequest) -> Result<IpcResponse> {
+    let device = queue_device(shared, request_device(&request)?).await?;
+    let request = audit::with_device(request, &device);
+    let reachable = {
+        let state = shared.state.read().await;
+        state.devices.iter().any(|d| d.id == device && d.reachable)
+    };
+    if reachable {
+        return audit::replay(shared, request).await;
+    }
+
+    let summary = audit::summarize(&request);
+    let now = Utc::now();
+    let hours = i64::try_from(shared.config.queue_expiry_hours).unwrap_or(i64::MAX);
+    let mut queue = shared.queue.lock().await;
+    let items = queue.load().await?;
+    let id = items.iter().map(|i| i.id).max().unwrap_or(0) + 1;
+    items.push(QueuedAction {
+        id,
+        device: device.clone(),
+        summary: summary.clone(),
+        request,
+        queued_at: now,
+        expires_at: now + Duration::try_hours(hours).unwrap_or(Duration::MAX),
+    });
+    queue.persist().await?;
+    Ok(into_response(Ok(format!(
+        "Queued {summary} for {device} (#{id}); it is sent when the device connects"
+    ))))
+}
+
+/// Replay everything queued for `device`, which just became reachable.
+pub async fn deliver(shared: &Shared, device: &str) {
+    let pending = match shared.queue.lock().await.take(|i| i.device == device).await {
+        Ok(pending) => pending,
+        Err(err) => {
+            eprintln!("action queue unavailable: {err:#}");
+            return;
+        }
+    };
+    for item in pending {
+        let outcome = match audit::replay(shared, item.request.clone()).await {
+            Ok(resp) => audit::outcome(&resp),
+            Err(err) => Err(err.to_string()),
+        };
+        if !shared.config.notifications_enabled {
+            continue;
+        }
+        match outcome {
+            Ok(()) => notify_local(
+                shared,
+                "Queued action delivered",
+                &format!("{} sent to {device}", item.summary),
+                NotificationUrgency::Low,
+            ),
+            Err(err) => notify_local(
+                shared,
+                "Queued action failed",
+                &format!("{} for {device}: {err}", item.summary),
+                NotificationUrgency::Normal,
+            ),
+        }
+    }
+}
+
+/// Drop queued actions whose expiry has passed.
+pub async fn expire(shared: &Shared) {
+    let now = Utc::now();
+    let expired = match shared.queue.lock().await.take(|i| i.expires_at <= now).await {
+        Ok(expired) => expired,
+        Err(err) => {
+            eprintln!("action queue unavailable: {err:#}");
+            return;
+        }
+    };
+    if !shared.config.notifications_enabled {
+        return;
+    }
+    for item in expired {
+        notify_local(
+            shared,
+            "Queued action expired",
+            &format!("{} for {} was not delivered", item.summary, item.device),
+            NotificationUrgency::Normal,
+        );
+    }
+}
+
+/// Like `resolve_device`, but accepts paired devices that are currently unreachable.
+async fn queue_device(shared: &Shared, requested: Option<String>) -> Result<String> {
+    let state = shared.state.read().await;
+    let paired = |id: &str| state.devices.iter().any(|d| d.id == id && d.paired);
+
+    if let Some(id) = requested {
+        if paired(&id) {
+            return Ok(id);
+        }
+        return Err(anyhow!("device '{id}' is not paired"));
+    }
+    if let Some(id) = shared.config.default_device.as_ref().filter(|id| paired(id)) {
+        return Ok(id.clone());
+    }
+    if let Some(device) = state.devices.iter().find(|d| d.reachable && d.paired) {
+        return Ok(device.id.clone());
+    }
+    let mut candidates = state.devices.iter().filter(|d| d.paired);
+    match (candidates.next(), candidates.next()) {
+        (Some(only), None) => Ok(only.id.clone()),
+        (Some(_), Some(_)) => Err(anyhow!(
+            "several paired devices are offline; pass --device to choose one"
+        )),
+        (None, _) => Err(anyhow!("no paired device to queue for")),
+    }
+}
+
+fn request_device(request: &IpcRequest) -> Result<Option<String>> {
+    match request {
+        IpcRequest::ShareFile { device, .. }
+        | IpcRequest::ShareFiles { device, .. }
+        | IpcRequest::ShareUrl { device, .. }
+        | IpcRequest::ShareText { device, .. }
+        | IpcRequest::Ping { device, .. } => Ok(device.clone()),
+        _ => Err(anyhow!("only share and ping requests can be queued")),
+    }
+}
+
+fn queue_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("queue.json"))
+}
@@ -0,0 +1,222 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use hyprconnect_core::{
+    looks_like_url, pick_clipboard_type, state_export_dir, ClipboardCapture, FileShareResult,
+};
+use tokio::process::Command;
+use zbus::proxy::CacheProperties;
+
+use crate::{resolve_device, run_kdeconnect, Shared};
+
+pub const SHARE_IFACE: &str = "org.kde.kdeconnect.device.share";
+/// Clipboard images are kept this long so kdeconnectd can finish reading them.
+const CLIPBOARD_FILE_TTL: Duration = Duration::from_secs(3600);
+
+enum ClipboardContent {
+    Text(String),
+    Image { data: Vec<u8>, extension: String },
+    Files(Vec<PathBuf>),
+}
+
+/// Share the clipboard according to its MIME type: file lists as files, images as a temporary
+/// file, URLs through the URL share path and anything else as text.
+///
+/// `capture` is normally read by `hyprconnectctl`; the daemon only reads the clipboard itself
+/// when the client could not, using the client's forwarded Wayland environment.
+pub async fn share_clipboard(
+    shared: &Shared,
+    device: Option<String>,
+    capture: Option<ClipboardCapture>,
+    primary: bool,
+    wayland_env: &[(String, String)],
+) -> Result<String> {
+    let capture = match capture {
+        Some(capture) => capture,
+        None => read_clipboard(primary, wayland_env).await?,
+    };
+    let content = decode_capture(capture)?;
+    let dev = resolve_device(shared, device).await?;
+    match content {
+        ClipboardContent::Text(text) => {
+            if looks_like_url(&text) {
+                run_kdeconnect(&["--device", &dev, "--share", &text]).await?;
+                Ok(format!("Shared URL to {dev}"))
+            } else {
+                share_text(&dev, &text).await?;
+                Ok(format!("Shared text to {dev}"))
+            }
+        }
+        ClipboardContent::Image { data, extension } => {
+            let path = write_clipboard_file(&data, &extension).await?;
+            run_kdeconnect(&["--device", &dev, "--share", &path.to_string_lossy()]).await?;
+            Ok(format!("Shared clipboard image to {dev}"))
+        }
+        ClipboardContent::Files(files) => {
+            for file in &files {
+                run_kdeconnect(&["--device", &dev, "--share", &file.to_string_lossy()]).await?;
+            }
+            Ok(format!("Shared {} file(s) to {dev}", files.len()))
+        }
+    }
+}
+
+/// Share each file separately so one unreadable path does not abort the rest.
+pub async fn share_files(device: &str, paths: &[String]) -> Vec<FileShareResult> {
+    let mut results = Vec::with_capacity(paths.len());
+    for path in paths {
+        let outcome = if !Path::new(path).is_absolute() {
+            Err(anyhow!("path must be absolute"))
+        } else if !Path::new(path).is_file() {
+            Err(anyhow!("not a regular file"))
+        } else {
+            run_kdeconnect(&["--device", device, "--share", path]).await.map(|_| ())
+        };
+        results.push(FileShareResult {
+            path: path.clone(),
+            ok: outcome.is_ok(),
+            error: outcome.err().map(|err| err.to_string()),
+        });
+    }
+    results
+}
+
+pub async fn share_text(device: &str, text: &str) -> Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{device}/share"))?
+        .interface(SHARE_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    proxy
+        .call_method("shareText", &(text,))
+        .await
+        .context("share plugin rejected the text")?;
+    Ok(())
+}
+
+async fn read_clipboard(primary: bool, env: &[(String, String)]) -> Result<ClipboardCapture> {
+    let types = wl_paste(primary, env, &["--list-types"])
+        .await
+        .context("failed to read clipboard with wl-paste")?;
+    let types = String::from_utf8_lossy(&types);
+    let offered: Vec<&str> = types.lines().map(str::trim).collect();
+    let mime = pick_clipboard_type(&offered).ok_or_else(|| anyhow!("clipboard is empty"))?;
+    let data = wl_paste(primary, env, &["--no-newline", "--type", &mime]).await?;
+    Ok(ClipboardCapture { mime, data })
+}
+
+fn decode_capture(capture: ClipboardCapture) -> Result<ClipboardContent> {
+    if capture.mime == "text/uri-list" {
+        let files = parse_uri_list(&String::from_utf8_lossy(&capture.data));
+        if !files.is_empty() {
+            return Ok(ClipboardContent::Files(files));
+        }
+    }
+    if let Some(subtype) = capture.mime.strip_prefix("image/") {
+        if !capture.data.is_empty() {
+            let extension = match subtype {
+                "jpeg" => "jpg".to_string(),
+                other => other.to_string(),
+            };
+            return Ok(ClipboardContent::Image {
+                data: capture.data,
+                extension,
+            });
+        }
+    }
+    let text = String::from_utf8_lossy(&capture.data).trim().to_string();
+    if text.is_empty() {
+        return Err(anyhow!("clipboard is empty"));
+    }
+    Ok(ClipboardContent::Text(text))
+}
+
+async fn wl_paste(primary: bool, env: &[(String, String)], args: &[&str]) -> Result<Vec<u8>> {
+    let mut cmd = Command::new("wl-paste");
+    if primary {
+        cmd.arg("--primary");
+    }
+    let output = cmd
+        .args(args)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("failed to execute wl-paste")?;
+    if !output.status.success() {
+        return Err(anyhow!("clipboard is empty"));
+    }
+    Ok(output.stdout)
+}
+
+/// Local files from a `text/uri-list` payload; remote URIs and comments are ignored.
+fn parse_uri_list(raw: &str) -> Vec<PathBuf> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.strip_prefix("file://"))
+        // `file://host/path` carries a host part; only the local form is supported.
+        .filter_map(|l| l.find('/').map(|idx| &l[idx..]))
+        .map(|p| PathBuf::from(percent_decode(p)))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+pub fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+async fn write_clipboard_file(data: &[u8], extension: &str) -> Result<PathBuf> {
+    let dir = state_export_dir().join("clipboard");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    prune_clipboard_files(&dir).await;
+
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("clipboard-{stamp}.{extension}"));
+    tokio::fs::write(&path, data)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+async fn prune_clipboard_files(dir: &Path) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    let now = SystemTime::now();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let expired = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > CLIPBOARD_FILE_TTL);
+        if expired {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use futures_util::StreamExt;
+use hyprconnect_core::{IpcResponse, NotificationUrgency, SmsAction, SmsMessage};
+use notify_rust::Notification;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedValue, StructureBuilder, Value};
+
+use crate::contacts::normalize_number;
+use crate::notifications::{desktop_urgency, ActionTarget};
+use crate::{into_data_response, into_response, require_plugin, resolve_device, Shared};
+
+const SMS_IFACE: &str = "org.kde.kdeconnect.device.sms";
+pub const CONVERSATIONS_IFACE: &str = "org.kde.kdeconnect.device.conversations";
+
+/// Android `Telephony.Sms.MESSAGE_TYPE_INBOX`.
+const MESSAGE_TYPE_INBOX: i32 = 1;
+
+/// Newest message date notified per `device/thread`, so re-fetched history never re-notifies.
+pub struct SmsTracker {
+    started_at: DateTime<Utc>,
+    last_notified: HashMap<String, DateTime<Utc>>,
+}
+
+impl Default for SmsTracker {
+    fn default() -> Self {
+        Self {
+            started_at: Utc::now(),
+            last_notified: HashMap::new(),
+        }
+    }
+}
+
+pub async fn send_sms(
+    shared: &Shared,
+    device: Option<String>,
+    recipients: &[String],
+    body: &str,
+    attachments: &[String],
+) -> Result<String> {
+    if recipients.is_empty() {
+        return Err(anyhow!("no SMS recipients given"));
+    }
+    if body.trim().is_empty() && attachments.is_empty() {
+        return Err(anyhow!("SMS message is empty"));
+    }
+    for path in attachments {
+        if !Path::new(path).is_absolute() || !Path::new(path).is_file() {
+            return Err(anyhow!("attachment not found: {path}"));
+        }
+    }
+
+    let dev = resolve_device(shared, device).await?;
+    require_plugin(&dev, "kdeconnect_sms").await?;
+
+    // Anything that is not a phone number is taken as a contact name.
+    let mut numbers = Vec::with_capacity(recipients.len());
+    {
+        let mut book = shared.contacts.lock().await;
+        for recipient in recipients {
+            if normalize_number(recipient).is_some() {
+                numbers.push(recipient.clone());
+            } else {
+                numbers.push(book.number_for(&dev, recipient).await?);
+            }
+        }
+    }
+
+    // sendSms(av addresses, s text, av attachmentUrls, x subId); each address is a `(s)` struct.
+    let addresses = numbers
+        .iter()
+        .map(|r| {
+            StructureBuilder::new()
+                .add_field(r.clone())
+                .build()
+                .map(Value::Structure)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let attachment_urls: Vec<Value> = attachments
+        .iter()
+        .map(|p| Value::new(format!("file://{p}")))
+        .collect();
+
+    let proxy = sms_proxy(&dev).await?;
+    proxy
+        .call_method("sendSms", &(addresses, body, attachment_urls, -1i64))
+        .await
+        .context("sms plugin rejected the message")?;
+
+    Ok(format!("SMS sent to {} via {dev}", recipients.join(", ")))
+}
+
+pub async fn handle_action(shared: &Shared, device: Option<String>, action: SmsAction) -> IpcResponse {
+    let dev = match resolve_device(shared, device).await {
+        Ok(dev) => dev,
+        Err(err) => return into_response(Err(err)),
+    };
+    let result = match action {
+        SmsAction::List => list_conversations(&dev).await,
+        SmsAction::Show { thread_id, limit } => show_conversation(&dev, thread_id, limit).await,
+    };
+    let result = match result {
+        Ok(mut messages) => {
+            for message in &mut messages {
+                message.contacts = contact_names(shared, &dev, &message.addresses).await;
+            }
+            Ok(messages)
+        }
+        Err(err) => Err(err),
+    };
+    into_data_response(result)
+}
+
+async fn list_conversations(device: &str) -> Result<Vec<SmsMessage>> {
+    require_plugin(device, "kdeconnect_sms").await?;
+    let conn = zbus::Connection::session().await?;
+    let proxy = conversations_proxy(&conn, device).await?;
+
+    let mut raw: Vec<OwnedValue> = proxy.call("activeConversations", &()).await?;
+    if raw.is_empty() {
+        // kdeconnectd only caches threads it has been asked for; fetch them and wait briefly.
+        proxy.call_method("requestAllConversationThreads", &()).await?;
+        let deadline = Instant::now() + Duration::from_secs(4);
+        while raw.is_empty() && Instant::now() < deadline {
+            sleep(Duration::from_millis(300)).await;
+            raw = proxy.call("activeConversations", &()).await?;
+        }
+    }
+
+    let mut threads: Vec<SmsMessage> = raw.iter().filter_map(|v| parse_message(v)).collect();
+    threads.sort_by_key(|m| std::cmp::Reverse(m.date));
+    Ok(threads)
+}
+
+async fn show_conversation(device: &str, thread_id: i64, limit: Option<u32>) -> Result<Vec<SmsMessage>> {
+    require_plugin(device, "kdeconnect_sms").await?;
+    let conn = zbus::Connection::session().await?;
+    let rule = zbus::MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .path(format!("/modules/kdeconnect/devices/{device}"))?
+        .interface(CONVERSATIONS_IFACE)?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &conn, None).await?;
+
+    let proxy = conversations_proxy(&conn, device).await?;
+    let end = limit.map(|l| l as i32).unwrap_or(-1);
+    proxy
+        .call_method("requestConversation", &(thread_id, 0i32, end))
+        .await?;
+
+    let mut messages: HashMap<i32, SmsMessage> = HashMap::new();
+    let collect = async {
+        while let Some(msg) = stream.next().await {
+            let msg = msg?;
+            let member = msg.header().member().map(|m| m.to_string()).unwrap_or_default();
+            match member.as_str() {
+                "conversationUpdated" | "conversationCreated" => {
+                    let Ok(value) = msg.body().deserialize::<OwnedValue>() else {
+                        continue;
+                    };
+                    if let Some(m) = parse_message(&value).filter(|m| m.thread_id == thread_id) {
+                        messages.insert(m.uid, m);
+                    }
+                }
+                "conversationLoaded" => {
+                    if let Ok((loaded, _count)) = msg.body().deserialize::<(i64, u64)>() {
+                        if loaded == thread_id {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+    // Older kdeconnectd versions never emit conversationLoaded; keep whatever arrived.
+    if let Ok(result) = timeout(Duration::from_secs(6), collect).await {
+        result?;
+    }
+
+    if messages.is_empty() {
+        return Err(anyhow!("no messages found for thread {thread_id}"));
+    }
+    let mut out: Vec<SmsMessage> = messages.into_values().collect();
+    out.sort_by_key(|m| m.date);
+    Ok(out)
+}
+
+pub async fn reply_to_thread(
+    conn: &zbus::Connection,
+    device: &str,
+    thread_id: i64,
+    text: &str,
+) -> Result<()> {
+    let proxy = conversations_proxy(conn, device).await?;
+    let attachments: Vec<Value> = Vec::new();
+    proxy
+        .call_method("replyToConversation", &(thread_id, text, attachments))
+        .await
+        .context("failed to reply to SMS conversation")?;
+    Ok(())
+}
+
+/// Notify about new incoming messages announced by conversationCreated/conversationUpdated.
+pub async fn handle_signal(shared: &Shared, device: &str, member: &str, value: OwnedValue) -> Result<()> {
+    if member != "conversationUpdated" && member != "conversationCreated" {
+        return Ok(());
+    }
+    if !shared.config.notifications_enabled || !shared.config.sms_notifications_enabled {
+        return Ok(());
+    }
+    let Some(message) = parse_message(&value) else {
+        return Ok(());
+    };
+    if !message.incoming {
+        return Ok(());
+    }
+
+    {
+        let mut tracker = shared.sms.lock().await;
+        if message.date <= tracker.started_at {
+            return Ok(());
+        }
+        let key = format!("{device}/{}", message.thread_id);
+        if tracker.last_notified.get(&key).is_some_and(|d| message.date <= *d) {
+            return Ok(());
+        }
+        tracker.last_notified.insert(key, message.date);
+    }
+
+    let names = contact_names(shared, device, &message.addresses).await;
+    let sender = message
+        .addresses
+        .iter()
+        .zip(&names)
+        .map(|(address, name)| if name.is_empty() { address } else { name })
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    let decision = shared.config.notification_rules.evaluate(
+        "SMS",
+        &sender,
+        &message.body,
+        NotificationUrgency::Normal,
+        Local::now(),
+    );
+    if !decision.show {
+        return Ok(());
+    }
+
+    let handle = Notification::new()
+        .appname("SMS")
+        .summary(&sender)
+        .body(&message.body)
+        .icon("mail-message-new")
+        .urgency(desktop_urgency(decision.urgency))
+        .action("reply", "Reply")
+        .show()
+        .context("failed to show SMS notification")?;
+    let target = ActionTarget::SmsThread {
+        device: device.to_string(),
+        thread_id: message.thread_id,
+    };
+    shared.mirror.lock().await.register_actions(handle.id(), target);
+    Ok(())
+}
+
+/// Contact name per address, or an empty string where the number is unknown.
+async fn contact_names(shared: &Shared, device: &str, addresses: &[String]) -> Vec<String> {
+    let mut book = shared.contacts.lock().await;
+    let mut names = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        names.push(book.lookup(device, address).await.unwrap_or_default());
+    }
+    names
+}
+
+/// Decode a ConversationMessage `(isa(s)xiixixa(xsss))`, tolerating trailing-field differences.
+fn parse_message(value: &Value) -> Option<SmsMessage> {
+    let structure = match value {
+        Value::Structure(s) => s,
+        Value::Value(inner) => return parse_message(inner),
+        _ => return None,
+    };
+    let fields = structure.fields();
+
+    let int = |idx: usize| match fields.get(idx) {
+        Some(Value::I32(v)) => Some(i64::from(*v)),
+        Some(Value::I64(v)) => Some(*v),
+        Some(Value::U32(v)) => Some(i64::from(*v)),
+        _ => None,
+    };
+    let body = match fields.get(1) {
+        Some(Value::Str(s)) => s.to_string(),
+        _ => return None,
+    };
+    let addresses = match fields.get(2) {
+        Some(Value::Array(items)) => items
+            .inner()
+            .iter()
+            .filter_map(|item| match item {
+                Value::Structure(a) => match a.fields().first() {
+                    Some(Value::Str(s)) => Some(s.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let date = DateTime::from_timestamp_millis(int(3)?)?;
+    Some(SmsMessage {
+        thread_id: int(6)?,
+        uid: int(7).unwrap_or_default() as i32,
+        addresses,
+        body,
+        date,
+        incoming: int(4) == Some(i64::from(MESSAGE_TYPE_INBOX)),
+        read: int(5).unwrap_or(1) != 0,
+        contacts: Vec::new(),
+    })
+}
+
+async fn conversations_proxy(conn: &zbus::Connection, device: &str) -> Result<zbus::Proxy<'static>> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{device}"))?
+        .interface(CONVERSATIONS_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
+
+async fn sms_proxy(device: &str) -> Result<zbus::Proxy<'static>> {
+    let conn = zbus::Connection::session().await?;
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+        .destination("org.kde.kdeconnect")?
+        .path(format!("/modules/kdeconnect/devices/{device}/sms"))?
+        .interface(SMS_IFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hyprconnect_core::{NotificationUrgency, Transfer, TransferState};
+use zbus::fdo::RequestNameFlags;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use crate::{notify_local, Shared};
+
+/// kdeconnectd reports job progress through KJobWidgets' KUiServerV2JobTracker, which talks to
+/// whoever owns this name (plasmashell on Plasma). Elsewhere nobody does, so hyprconnectd serves it.
+const SERVER_NAME: &str = "org.kde.JobViewServer";
+const SERVER_PATH: &str = "/JobViewServer";
+const VIEW_PATH_PREFIX: &str = "/org/kde/hyprconnect/jobs";
+/// `KIO::ERR_USER_CANCELED`, also used by `KJob::KilledJobError`.
+const ERR_USER_CANCELED: u32 = 1;
+/// Finished transfers kept for `hyprconnectctl transfers`.
+const FINISHED_LIMIT: usize = 50;
+
+#[derive(Default)]
+pub struct TransferTracker {
+    next_id: u32,
+    transfers: Vec<Transfer>,
+}
+
+impl TransferTracker {
+    pub fn list(&self) -> Vec<Transfer> {
+        self.transfers.clone()
+    }
+
+    fn get_mut(&mut self, id: u32) -> Option<&mut Transfer> {
+        self.transfers.iter_mut().find(|t| t.id == id)
+    }
+
+    fn prune(&mut self) {
+        let finished = self
+            .transfers
+            .iter()
+            .filter(|t| t.state != TransferState::Running)
+            .count();
+        let mut excess = finished.saturating_sub(FINISHED_LIMIT);
+        self.transfers.retain(|t| {
+            if excess > 0 && t.state != TransferState::Running {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+/// Own `org.kde.JobViewServer` and record kdeconnectd's jobs until the connection goes away.
+pub async fn serve(shared: Shared) -> Result<()> {
+    let conn = zbus::connection::Builder::session()?
+        .serve_at(SERVER_PATH, JobViewServer { shared })?
+        .build()
+        .await?;
+    // Let a real job view server (e.g. plasmashell started later) take over.
+    conn.request_name_with_flags(
+        SERVER_NAME,
+        RequestNameFlags::AllowReplacement | RequestNameFlags::DoNotQueue,
+    )
+    .await
+    .context("org.kde.JobViewServer is owned by another process; transfer tracking disabled")?;
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+struct JobViewServer {
+    shared: Shared,
+}
+
+#[zbus::interface(name = "org.kde.JobViewServerV2")]
+impl JobViewServer {
+    #[zbus(name = "requestView")]
+    async fn request_view(
+        &self,
+        #[zbus(object_server)] server: &zbus::ObjectServer,
+        desktop_entry: String,
+        _capabilities: i32,
+        hints: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<OwnedObjectPath> {
+        let id = {
+            let mut tracker = self.shared.transfers.lock().await;
+            tracker.next_id += 1;
+            tracker.next_id
+        };
+        let path = format!("{VIEW_PATH_PREFIX}/{id}");
+        // Other applications get a view too, so their jobs do not error out; only
+        // kdeconnectd's jobs are recorded.
+        let tracked = desktop_entry.contains("kdeconnect");
+        if tracked {
+            let mut transfer = Transfer {
+                id,
+                device: None,
+                title: String::new(),
+                file: None,
+                incoming: false,
+                state: TransferState::Running,
+                processed_bytes: 0,
+                total_bytes: None,
+                speed: 0,
+                percent: None,
+                error: None,
+                started_at: Utc::now(),
+                finished_at: None,
+            };
+            apply_update(&self.shared, &mut transfer, &hints).await;
+            let mut tracker = self.shared.transfers.lock().await;
+            tracker.transfers.push(transfer);
+            tracker.prune();
+        }
+
+        let view = JobView {
+            shared: self.shared.clone(),
+            id,
+            tracked,
+        };
+        server.at(path.as_str(), view).await?;
+        OwnedObjectPath::try_from(path).map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+}
+
+struct JobView {
+    shared: Shared,
+    id: u32,
+    tracked: bool,
+}
+
+#[zbus::interface(name = "org.kde.JobViewV3")]
+impl JobView {
+    #[zbus(name = "update")]
+    async fn update(&self, properties: HashMap<String, OwnedValue>) {
+        if !self.tracked {
+            return;
+        }
+        let Some(mut transfer) = self.snapshot().await else {
+            return;
+        };
+        apply_update(&self.shared, &mut transfer, &properties).await;
+        if let Some(slot) = self.shared.transfers.lock().await.get_mut(self.id) {
+            *slot = transfer;
+        }
+    }
+
+    #[zbus(name = "terminate")]
+    async fn terminate(
+        &self,
+        #[zbus(object_server)] server: &zbus::ObjectServer,
+        #[zbus(header)] header: zbus::message::Header<'_>,
+        error_code: u32,
+        error_message: String,
+        _hints: HashMap<String, OwnedValue>,
+    ) {
+        if let Some(path) = header.path() {
+            let _ = server.remove::<JobView, _>(path.to_owned()).await;
+        }
+        if !self.tracked {
+            return;
+        }
+
+        let finished = {
+            let mut tracker = self.shared.transfers.lock().await;
+            let Some(transfer) = tracker.get_mut(self.id) else {
+                return;
+            };
+            transfer.state = match error_code {
+                0 => TransferState::Completed,
+                ERR_USER_CANCELED => TransferState::Cancelled,
+                _ => TransferState::Failed,
+            };
+            if transfer.state == TransferState::Completed {
+                if let Some(total) = transfer.total_bytes {
+                    transfer.processed_bytes = total;
+                }
+                transfer.percent = Some(100);
+            } else if !error_message.is_empty() {
+                transfer.error = Some(error_message);
+            }
+            transfer.speed = 0;
+            transfer.finished_at = Some(Utc::now());
+            transfer.clone()
+        };
+        notify_finished(&self.shared, &finished);
+    }
+}
+
+impl JobView {
+    async fn snapshot(&self) -> Option<Transfer> {
+        let tracker = self.shared.transfers.lock().await;
+        tracker.transfers.iter().find(|t| t.id == self.id).cloned()
+    }
+}
+
+/// Apply KUiServerV2JobTracker's property map (`title`, `descriptionValue1`, `processedBytes`,
+/// `totalBytes`, `speed`, `percent`, ...).
+async fn apply_update(shared: &Shared, transfer: &mut Transfer, properties: &HashMap<String, OwnedValue>) {
+    if let Some(title) = string_prop(properties, "title") {
+        transfer.title = title;
+        if transfer.device.is_none() {
+            transfer.device = device_from_title(shared, &transfer.title).await;
+        }
+    }
+    if let Some(file) = string_prop(properties, "descriptionValue1") {
+        transfer.file = Some(file);
+    }
+    // Only receiving jobs carry a destination, as `destUrl` or a second "To" description field;
+    // the title is localized, so it cannot tell the direction.
+    if properties.contains_key("destUrl") || properties.contains_key("descriptionValue2") {
+        transfer.incoming = true;
+    }
+    if let Some(total) = int_prop(properties, "totalBytes") {
+        transfer.total_bytes = Some(total);
+    }
+    if let Some(processed) = int_prop(properties, "processedBytes") {
+        transfer.processed_bytes = processed;
+    }
+    if let Some(speed) = int_prop(properties, "speed") {
+        transfer.speed = speed;
+    }
+    if let Some(percent) = int_prop(properties, "percent") {
+        transfer.percent = Some(percent.min(100) as u32);
+    }
+}
+
+/// kdeconnectd titles jobs "Sending to <device name>" / "Receiving file from <device name>".
+async fn device_from_title(shared: &Shared, title: &str) -> Option<String> {
+    let state = shared.state.read().await;
+    state
+        .devices
+        .iter()
+        .filter(|d| !d.name.is_empty() && title.contains(d.name.as_str()))
+        .max_by_key(|d| d.name.len())
+        .map(|d| d.id.clone())
+}
+
+fn string_prop(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    match properties.get(key).map(|v| &**v) {
+        Some(Value::Str(s)) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn int_prop(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<u64> {
+    match properties.get(key).map(|v| &**v) {
+        Some(Value::U64(n)) => Some(*n),
+        Some(Value::I64(n)) => u64::try_from(*n).ok(),
+        Some(Value::U32(n)) => Some(u64::from(*n)),
+        Some(Value::I32(n)) => u64::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+fn notify_finished(shared: &Shared, transfer: &Transfer) {
+    if !shared.config.notifications_enabled || !shared.config.transfer_notifications_enabled {
+        return;
+    }
+    let name = transfer
+        .file
+        .clone()
+        .unwrap_or_else(|| transfer.title.clone());
+    // Received files get their own notification, with open actions, from the incoming router.
+    if transfer.state == TransferState::Completed
+        && shared.config.incoming_notifications_enabled
+        && transfer.incoming
+    {
+        return;
+    }
+    let (summary, urgency) = match transfer.state {
+        TransferState::Completed => ("Transfer complete", NotificationUrgency::Low),
+        TransferState::Failed => ("Transfer failed", NotificationUrgency::Normal),
+        TransferState::Cancelled | TransferState::Running => return,
+    };
+    let body = match &transfer.error {
+        Some(err) => format!("{name}: {err}"),
+        None => name,
+    };
+    notify_local(shared, summary, &body, urgency);
+}